@@ -0,0 +1,134 @@
+// Compare the owned and borrowed parsing APIs on a generated log.
+// The log is 1 GB by default, its size in bytes can be changed using GST_LOG_BENCH_SIZE.
+
+#[macro_use]
+extern crate criterion;
+use criterion::Criterion;
+use gst_log_parser::{parse_streaming, try_parse, Multiline};
+use std::env;
+use std::fs::{self, File};
+use std::io::{BufWriter, Write};
+use std::path::PathBuf;
+
+const ENTRIES: &[(&str, &str, &str, &str)] = &[
+    (
+        "DEBUG",
+        "GST_PADS",
+        "gstpad.c:4392:gst_pad_chain_data_unchecked:<queue0:sink>",
+        "called chainfunction &gst_queue_chain with buffer 0x7f3c1c0093a0, returned ok",
+    ),
+    (
+        "LOG",
+        "GST_SCHEDULING",
+        "gstpad.c:4295:gst_pad_push_data:<videotestsrc0:src>",
+        "pushing buffer 0x7f3c1c0093a0",
+    ),
+    (
+        "TRACE",
+        "GST_TRACER",
+        "gsttracerrecord.c:111:gst_tracer_record_log:",
+        "buffer, ts=(guint64)1234, pad-ix=(uint)4, element-ix=(uint)3, buffer-pts=(guint64)0;",
+    ),
+    (
+        "INFO",
+        "GST_STATES",
+        "gstelement.c:2676:gst_element_continue_state:<pipeline0>",
+        "completed state change to PLAYING",
+    ),
+];
+
+fn generate_log() -> PathBuf {
+    let size: u64 = env::var("GST_LOG_BENCH_SIZE")
+        .ok()
+        .and_then(|s| s.parse().ok())
+        .unwrap_or(1 << 30);
+    let path = env::temp_dir().join(format!("gst-log-parser-bench-{}.log", size));
+    if path.exists() {
+        return path;
+    }
+
+    // Write to a temporary file first so an interrupted run never leaves a truncated log behind
+    let tmp = path.with_extension("tmp");
+    let f = File::create(&tmp).expect("Failed to create log file");
+    let mut out = BufWriter::new(f);
+    let mut written = 0;
+    let mut ts: u64 = 0;
+
+    for i in 0.. {
+        if written >= size {
+            break;
+        }
+
+        let (level, category, location, message) = ENTRIES[i % ENTRIES.len()];
+        let thread = format!("0x55895101{:04x}", i % 4);
+        let time = format!(
+            "{}:{:02}:{:02}.{:09}",
+            ts / 3_600_000_000_000,
+            (ts / 60_000_000_000) % 60,
+            (ts / 1_000_000_000) % 60,
+            ts % 1_000_000_000
+        );
+        // Use colors for some of the lines
+        let line = if i % 4 == 0 {
+            format!(
+                "{} \x1b[336m{:5}\x1b[00m {:>14} \x1b[37m{:<7}\x1b[00m \x1b[00;01;34m{:>20} {}\x1b[00m {}\n",
+                time, 8874, thread, level, category, location, message
+            )
+        } else {
+            format!(
+                "{} {:5} {:>14} {:<7} {:>20} {} {}\n",
+                time, 8874, thread, level, category, location, message
+            )
+        };
+
+        out.write_all(line.as_bytes())
+            .expect("Failed to write log file");
+        written += line.len() as u64;
+        ts += 12345;
+    }
+
+    out.flush().expect("Failed to write log file");
+    drop(out);
+    fs::rename(&tmp, &path).expect("Failed to rename log file");
+    path
+}
+
+fn owned(c: &mut Criterion) {
+    let path = generate_log();
+
+    c.bench_function("owned", move |b| {
+        b.iter(|| {
+            let f = File::open(&path).expect("Failed to open log file");
+            // The streaming parser doesn't handle multi-line messages
+            try_parse(f)
+                .multiline(Multiline::Disabled)
+                .filter_map(Result::ok)
+                .count()
+        })
+    });
+}
+
+fn borrowed(c: &mut Criterion) {
+    let path = generate_log();
+
+    c.bench_function("borrowed", move |b| {
+        b.iter(|| {
+            let f = File::open(&path).expect("Failed to open log file");
+            let mut parsed = parse_streaming(f);
+            let mut n = 0;
+            while let Some(entry) = parsed.next_entry() {
+                if entry.is_ok() {
+                    n += 1;
+                }
+            }
+            n
+        })
+    });
+}
+
+criterion_group! {
+    name = benches;
+    config = Criterion::default().sample_size(10);
+    targets = owned, borrowed
+}
+criterion_main!(benches);
@@ -1,7 +1,12 @@
-use std::fs::File;
-
 extern crate gst_log_parser;
-use gst_log_parser::parse;
+#[cfg(feature = "serialize")]
+use gst_log_parser::FlatEntry;
+use gst_log_parser::{can_follow, open_log, try_parse, Entry, Filter, Writer};
+use std::io;
+use std::io::Write;
+#[cfg(not(feature = "serialize"))]
+use std::process;
+use std::str::FromStr;
 
 extern crate structopt;
 extern crate structopt_derive;
@@ -13,16 +18,153 @@ use structopt::StructOpt;
     about = "Parse a GStreamer log file and dump its content. Mostly used for testing"
 )]
 struct Opt {
-    #[structopt(help = "Input file")]
+    #[structopt(help = "Input file, or '-' for stdin")]
     input: String,
+    #[structopt(
+        short = "f",
+        long = "follow",
+        help = "Wait for more data once the end of the input has been reached"
+    )]
+    follow: bool,
+    #[structopt(
+        short = "c",
+        long = "color",
+        help = "Color the output as GStreamer does"
+    )]
+    color: bool,
+    #[structopt(
+        long = "format",
+        default_value = "text",
+        help = "Output format: text, json, jsonl or csv"
+    )]
+    format: Format,
+    #[structopt(
+        long = "filter",
+        help = "Only process entries matching this filter, such as 'level<=WARN && category~v4l2*' or a GST_DEBUG list"
+    )]
+    filter: Option<Filter>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum Format {
+    Text,
+    Json,
+    Jsonl,
+    Csv,
+}
+
+impl FromStr for Format {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Format, String> {
+        match s {
+            "text" => Ok(Format::Text),
+            "json" => Ok(Format::Json),
+            "jsonl" => Ok(Format::Jsonl),
+            "csv" => Ok(Format::Csv),
+            _ => Err(format!("unknown format '{}'", s)),
+        }
+    }
+}
+
+enum Output<W: Write> {
+    Text(Writer<W>),
+    #[cfg(feature = "serialize")]
+    Json {
+        writer: W,
+        first: bool,
+    },
+    #[cfg(feature = "serialize")]
+    Jsonl(W),
+    #[cfg(feature = "serialize")]
+    Csv(Box<csv::Writer<W>>),
+}
+
+impl<W: Write> Output<W> {
+    #[cfg(feature = "serialize")]
+    fn new(format: Format, writer: W, color: bool) -> Output<W> {
+        match format {
+            Format::Text => Output::Text(Writer::new(writer).colors(color)),
+            Format::Json => Output::Json {
+                writer,
+                first: true,
+            },
+            Format::Jsonl => Output::Jsonl(writer),
+            Format::Csv => Output::Csv(Box::new(csv::Writer::from_writer(writer))),
+        }
+    }
+
+    #[cfg(not(feature = "serialize"))]
+    fn new(format: Format, writer: W, color: bool) -> Output<W> {
+        if format != Format::Text {
+            eprintln!("Rebuild with the 'serialize' feature to export entries");
+            process::exit(1);
+        }
+        Output::Text(Writer::new(writer).colors(color))
+    }
+
+    fn write(&mut self, entry: &Entry) -> io::Result<()> {
+        match self {
+            Output::Text(writer) => writer.write(entry),
+            #[cfg(feature = "serialize")]
+            Output::Json { writer, first } => {
+                writer.write_all(if *first { b"[\n" } else { b",\n" })?;
+                *first = false;
+                serde_json::to_writer(&mut *writer, entry)?;
+                Ok(())
+            }
+            #[cfg(feature = "serialize")]
+            Output::Jsonl(writer) => {
+                serde_json::to_writer(&mut *writer, entry)?;
+                writer.write_all(b"\n")
+            }
+            #[cfg(feature = "serialize")]
+            Output::Csv(writer) => writer
+                .serialize(FlatEntry::from(entry))
+                .map_err(|e| io::Error::new(io::ErrorKind::Other, e)),
+        }
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        match self {
+            Output::Text(writer) => writer.flush(),
+            #[cfg(feature = "serialize")]
+            Output::Json { writer, .. } | Output::Jsonl(writer) => writer.flush(),
+            #[cfg(feature = "serialize")]
+            Output::Csv(writer) => writer.flush(),
+        }
+    }
+
+    fn finish(&mut self) -> io::Result<()> {
+        #[cfg(feature = "serialize")]
+        {
+            if let Output::Json { writer, first } = self {
+                writer.write_all(if *first { b"[]\n" } else { b"\n]\n" })?;
+            }
+        }
+        self.flush()
+    }
 }
 
 fn main() {
     let opt = Opt::from_args();
-    let f = File::open(opt.input).expect("Failed to open log file");
+    // Pipes are over once their end has been reached
+    let follow = opt.follow && can_follow(&opt.input);
+    let f = open_log(opt.input).expect("Failed to open log file");
+
+    let stdout = io::stdout();
+    let mut output = Output::new(opt.format, stdout.lock(), opt.color);
 
-    let parsed = parse(f);
+    let filter = opt.filter.unwrap_or_default();
+    let parsed = try_parse(f)
+        .follow(follow)
+        .filter_map(Result::ok)
+        .filter(|entry| filter.matches(entry));
     for entry in parsed {
-        println!("{}", entry);
+        output.write(&entry).expect("Failed to write entry");
+        if opt.follow {
+            output.flush().expect("Failed to write entry");
+        }
     }
+    output.finish().expect("Failed to write entry");
 }
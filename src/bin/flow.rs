@@ -1,12 +1,10 @@
 // Generate input logs with: GST_DEBUG="GST_TRACER:7" GST_TRACERS=stats
 
-use failure::Error;
+use failure::{format_err, Error};
 use gnuplot::*;
-use gst_log_parser::parse;
-use gstreamer::{ClockTime, DebugLevel, Structure};
+use gst_log_parser::{can_follow, open_log, try_parse, ClockTime, DebugLevel, Filter, Structure};
 use std::collections::HashMap;
 use std::fmt;
-use std::fs::File;
 use std::path::PathBuf;
 use structopt::StructOpt;
 
@@ -31,10 +29,21 @@ enum Command {
 #[derive(StructOpt, Debug)]
 #[structopt(name = "flow", about = "Process logs generated by the 'stats' tracer")]
 struct Opt {
-    #[structopt(parse(from_os_str))]
+    #[structopt(parse(from_os_str), help = "Input file, or '-' for stdin")]
     input: PathBuf,
+    #[structopt(
+        short = "f",
+        long = "follow",
+        help = "Wait for more data once the end of the input has been reached, not supported by the plot commands"
+    )]
+    follow: bool,
     #[structopt(subcommand)]
     command: Command,
+    #[structopt(
+        long = "filter",
+        help = "Only process entries matching this filter, such as 'level<=WARN && category~v4l2*' or a GST_DEBUG list"
+    )]
+    filter: Option<Filter>,
 }
 
 #[derive(Debug)]
@@ -87,8 +96,9 @@ impl fmt::Display for Pad {
 #[derive(Debug)]
 struct Flow {
     command: Command,
-    elements: HashMap<u32, Element>,
-    pads: HashMap<u32, Pad>,
+    // Indexed by pid and tracer index, as merged logs may contain several processes
+    elements: HashMap<(u32, u32), Element>,
+    pads: HashMap<(u32, u32), Pad>,
 }
 
 impl Flow {
@@ -100,41 +110,41 @@ impl Flow {
         }
     }
 
-    fn parse(&mut self, s: &Structure) {
+    fn parse(&mut self, pid: u32, s: &Structure) {
         match s.get_name() {
             "new-element" => {
                 let idx = s.get::<u32>("ix").unwrap();
                 self.elements
-                    .entry(idx)
+                    .entry((pid, idx))
                     .or_insert_with(|| Element::new(s.get::<&str>("name").unwrap()));
             }
             "new-pad" => {
                 let idx = s.get::<u32>("ix").unwrap();
                 let parent_ix = s.get::<u32>("parent-ix").unwrap();
-                let element_name = match self.elements.get(&parent_ix) {
+                let element_name = match self.elements.get(&(pid, parent_ix)) {
                     None => None,
                     Some(e) => Some(e.name.clone()),
                 };
 
                 self.pads
-                    .entry(idx)
+                    .entry((pid, idx))
                     .or_insert_with(|| Pad::new(s.get::<&str>("name").unwrap(), element_name));
             }
             "buffer" => {
-                self.handle_buffer(s);
+                self.handle_buffer(pid, s);
             }
             _ => {}
         }
     }
 
-    fn handle_buffer(&mut self, s: &Structure) {
+    fn handle_buffer(&mut self, pid: u32, s: &Structure) {
         let pad = self
             .pads
-            .get_mut(&s.get::<u32>("pad-ix").unwrap())
+            .get_mut(&(pid, s.get::<u32>("pad-ix").unwrap()))
             .expect("Unknown pad");
         let element = self
             .elements
-            .get(&s.get::<u32>("element-ix").unwrap())
+            .get(&(pid, s.get::<u32>("element-ix").unwrap()))
             .expect("Unknown element");
 
         if pad.element_name.is_none() {
@@ -230,19 +240,29 @@ impl Flow {
 
 fn main() -> Result<(), Error> {
     let opt = Opt::from_args();
-    let input = File::open(opt.input)?;
+    // Plots are only drawn once the whole input has been processed
+    if opt.follow && (opt.command == Command::PlotPts || opt.command == Command::PlotDts) {
+        return Err(format_err!("--follow can't be used to plot"));
+    }
+    // Pipes are over once their end has been reached
+    let follow = opt.follow && can_follow(&opt.input);
+    let input = open_log(opt.input)?;
     let mut flow = Flow::new(opt.command);
+    let filter = opt.filter.unwrap_or_default();
 
-    let parsed = parse(input)
-        .filter(|entry| entry.category == "GST_TRACER" && entry.level == DebugLevel::Trace);
+    let parsed = try_parse(input)
+        .follow(follow)
+        .filter_map(Result::ok)
+        .filter(|entry| entry.category == "GST_TRACER" && entry.level == DebugLevel::Trace)
+        .filter(|entry| filter.matches(entry));
 
     for entry in parsed {
-        let s = match entry.message_to_struct() {
+        let s = match entry.message_structure() {
             None => continue,
             Some(s) => s,
         };
 
-        flow.parse(&s);
+        flow.parse(entry.pid, &s);
     }
 
     flow.plot();
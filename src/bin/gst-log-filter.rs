@@ -0,0 +1,201 @@
+use std::collections::{HashMap, VecDeque};
+use std::io;
+use std::io::Write;
+use std::path::PathBuf;
+
+use failure::{format_err, Error};
+use gst_log_parser::{can_follow, open_log, try_parse, Entry, Filter, Writer};
+use regex::Regex;
+use structopt::StructOpt;
+
+#[derive(StructOpt, Debug)]
+#[structopt(
+    name = "gst-log-filter",
+    about = "Keep only the log entries matching the given criteria, in GStreamer's log format"
+)]
+struct Opt {
+    #[structopt(parse(from_os_str), help = "Input file, or '-' for stdin")]
+    input: PathBuf,
+    #[structopt(
+        short = "d",
+        long = "debug",
+        help = "Debug thresholds, using the GST_DEBUG syntax such as '*:2,v4l2*:6,GST_PADS:5'"
+    )]
+    debug: Option<String>,
+    #[structopt(
+        long = "from",
+        help = "Skip entries before this running time, such as '1.5s'"
+    )]
+    from: Option<String>,
+    #[structopt(
+        long = "to",
+        help = "Skip entries after this running time, such as '0:00:05.0'"
+    )]
+    to: Option<String>,
+    #[structopt(short = "t", long = "thread", help = "Keep entries from this thread")]
+    threads: Vec<String>,
+    #[structopt(short = "p", long = "pid", help = "Keep entries from this process")]
+    pids: Vec<u32>,
+    #[structopt(
+        short = "o",
+        long = "object",
+        help = "Keep entries logged for an object matching this glob pattern"
+    )]
+    objects: Vec<String>,
+    #[structopt(
+        short = "e",
+        long = "regex",
+        help = "Keep entries whose message matches this regular expression"
+    )]
+    regexes: Vec<Regex>,
+    #[structopt(
+        short = "B",
+        long = "before-context",
+        help = "Print this number of entries from the same thread before matching entries"
+    )]
+    before: Option<usize>,
+    #[structopt(
+        short = "A",
+        long = "after-context",
+        help = "Print this number of entries from the same thread after matching entries"
+    )]
+    after: Option<usize>,
+    #[structopt(
+        short = "C",
+        long = "context",
+        help = "Print this number of entries from the same thread around matching entries"
+    )]
+    context: Option<usize>,
+    #[structopt(
+        long = "filter",
+        help = "Only process entries matching this filter, such as 'level<=WARN && category~v4l2*' or a GST_DEBUG list"
+    )]
+    filter: Option<Filter>,
+    #[structopt(
+        short = "c",
+        long = "color",
+        help = "Color the output as GStreamer does"
+    )]
+    color: bool,
+    #[structopt(
+        short = "f",
+        long = "follow",
+        help = "Wait for more data once the end of the input has been reached"
+    )]
+    follow: bool,
+}
+
+// Quote @value so it can be used in a filter expression
+fn quote(value: &str) -> String {
+    format!("\"{}\"", value.replace('\\', "\\\\").replace('"', "\\\""))
+}
+
+fn condition(field: &str, op: &str, value: &str) -> Result<Filter, Error> {
+    Filter::parse(&format!("{} {} {}", field, op, quote(value)))
+        .map_err(|err| format_err!("Invalid {} '{}': {}", field, value, err.reason))
+}
+
+// Accept entries matching any of the @values
+fn any_of<T: ToString>(field: &str, op: &str, values: &[T]) -> Result<Filter, Error> {
+    let mut filters = values.iter().map(|v| condition(field, op, &v.to_string()));
+
+    match filters.next() {
+        None => Ok(Filter::default()),
+        Some(first) => filters.fold(first, |acc, filter| Ok(acc?.or(filter?))),
+    }
+}
+
+impl Opt {
+    fn to_filter(&self) -> Result<Filter, Error> {
+        let mut filter = self.filter.clone().unwrap_or_default();
+
+        if let Some(debug) = &self.debug {
+            let debug = Filter::parse(debug)
+                .map_err(|err| format_err!("Invalid debug thresholds: {}", err))?;
+            filter = filter.and(debug);
+        }
+        if let Some(from) = &self.from {
+            filter = filter.and(condition("ts", ">=", from)?);
+        }
+        if let Some(to) = &self.to {
+            filter = filter.and(condition("ts", "<=", to)?);
+        }
+
+        Ok(filter
+            .and(any_of("thread", "==", &self.threads)?)
+            .and(any_of("pid", "==", &self.pids)?)
+            .and(any_of("object", "~", &self.objects)?))
+    }
+}
+
+#[derive(Default)]
+struct ThreadContext {
+    // Latest entries which have not been printed
+    before: VecDeque<Entry>,
+    // Number of entries still to be printed after the latest match
+    after: usize,
+}
+
+// Context is kept per thread so the entries printed around a match are the ones related to it,
+// as a result they may not be in the same order as in the input.
+struct Context<W: Write> {
+    writer: Writer<W>,
+    before: usize,
+    after: usize,
+    threads: HashMap<String, ThreadContext>,
+}
+
+impl<W: Write> Context<W> {
+    fn process(&mut self, entry: Entry, matched: bool) -> io::Result<()> {
+        let thread = self.threads.entry(entry.thread.clone()).or_default();
+
+        if matched {
+            for e in thread.before.drain(..) {
+                self.writer.write(&e)?;
+            }
+            self.writer.write(&entry)?;
+            thread.after = self.after;
+        } else if thread.after > 0 {
+            self.writer.write(&entry)?;
+            thread.after -= 1;
+        } else if self.before > 0 {
+            if thread.before.len() == self.before {
+                thread.before.pop_front();
+            }
+            thread.before.push_back(entry);
+        }
+
+        Ok(())
+    }
+}
+
+fn main() -> Result<(), Error> {
+    let opt = Opt::from_args();
+    let filter = opt.to_filter()?;
+    // Pipes are over once their end has been reached
+    let follow = opt.follow && can_follow(&opt.input);
+    let input = open_log(&opt.input)?;
+
+    let stdout = io::stdout();
+    let mut context = Context {
+        writer: Writer::new(stdout.lock()).colors(opt.color),
+        before: opt.before.or(opt.context).unwrap_or(0),
+        after: opt.after.or(opt.context).unwrap_or(0),
+        threads: HashMap::new(),
+    };
+
+    let parsed = try_parse(input).follow(follow).filter_map(Result::ok);
+    for entry in parsed {
+        let matched = filter.matches(&entry)
+            && (opt.regexes.is_empty() || opt.regexes.iter().any(|r| r.is_match(&entry.message)));
+
+        context.process(entry, matched)?;
+        if opt.follow {
+            context.writer.flush()?;
+        }
+    }
+
+    context.writer.flush()?;
+
+    Ok(())
+}
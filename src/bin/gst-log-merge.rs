@@ -0,0 +1,102 @@
+use std::io;
+use std::path::PathBuf;
+
+use failure::{format_err, Error};
+use gst_log_parser::{open_log, parse, Alignment, ClockTime, Filter, Merger, Writer};
+use structopt::StructOpt;
+
+#[derive(StructOpt, Debug)]
+#[structopt(
+    name = "gst-log-merge",
+    about = "Merge logs from several processes onto one timeline, in GStreamer's log format"
+)]
+struct Opt {
+    #[structopt(parse(from_os_str), help = "Input files")]
+    inputs: Vec<PathBuf>,
+    #[structopt(
+        short = "w",
+        long = "wallclock",
+        help = "Align the logs using their wall-clock timestamps"
+    )]
+    wallclock: bool,
+    #[structopt(
+        short = "a",
+        long = "anchor",
+        help = "Align the logs on the first entry matching this filter in each of them"
+    )]
+    anchor: Option<Filter>,
+    #[structopt(
+        long = "offset",
+        parse(try_from_str = "parse_offset"),
+        help = "Shift the input with the given index, starting at 0, such as '1=-1.5s'"
+    )]
+    offsets: Vec<(usize, i64)>,
+    #[structopt(
+        long = "filter",
+        help = "Only process entries matching this filter, such as 'level<=WARN && category~v4l2*' or a GST_DEBUG list"
+    )]
+    filter: Option<Filter>,
+    #[structopt(
+        short = "c",
+        long = "color",
+        help = "Color the output as GStreamer does"
+    )]
+    color: bool,
+}
+
+// Parse an offset such as "1=-1.5s"
+fn parse_offset(s: &str) -> Result<(usize, i64), String> {
+    let invalid = || format!("invalid offset '{}'", s);
+    let mut split = s.splitn(2, '=');
+    let index = split
+        .next()
+        .and_then(|index| index.parse().ok())
+        .ok_or_else(invalid)?;
+    let time = split.next().ok_or_else(invalid)?;
+    let (sign, time) = match time.strip_prefix('-') {
+        Some(time) => (-1, time),
+        None => (1, time),
+    };
+    let time = time
+        .parse::<ClockTime>()
+        .ok()
+        .and_then(|time| time.nanoseconds())
+        .ok_or_else(invalid)?;
+
+    Ok((index, sign * time as i64))
+}
+
+fn main() -> Result<(), Error> {
+    let opt = Opt::from_args();
+
+    let alignment = match (opt.wallclock, opt.anchor) {
+        (true, Some(_)) => return Err(format_err!("--wallclock and --anchor can't be combined")),
+        (true, None) => Alignment::Wallclock,
+        (false, Some(anchor)) => Alignment::Anchor(anchor),
+        (false, None) => Alignment::RunningTime,
+    };
+
+    let mut inputs = Vec::new();
+    for path in &opt.inputs {
+        inputs.push(parse(open_log(path)?));
+    }
+
+    let mut merger = Merger::new(inputs).alignment(alignment);
+    for (index, offset) in opt.offsets {
+        merger = merger.offset(index, offset);
+    }
+
+    let filter = opt.filter.unwrap_or_default();
+    let merged = merger
+        .merge()?
+        .filter(|merged| filter.matches(&merged.entry));
+
+    let stdout = io::stdout();
+    let mut writer = Writer::new(stdout.lock()).colors(opt.color);
+    for merged in merged {
+        writer.write(&merged.entry)?;
+    }
+    writer.flush()?;
+
+    Ok(())
+}
@@ -0,0 +1,276 @@
+// Import a log into a SQLite database. Both ts and wallclock are stored in nanoseconds.
+// For example, to count warnings per category and minute:
+// SELECT ts / 60000000000 AS minute, category, COUNT(*) FROM entries
+//   WHERE level = 'WARN' GROUP BY minute, category;
+use std::convert::TryFrom;
+use std::path::PathBuf;
+use std::time::UNIX_EPOCH;
+
+use failure::{format_err, Error};
+use gst_log_parser::{open_log, parse, Entry, Filter, Value};
+use rusqlite::types::Value as SqlValue;
+use rusqlite::{params, Connection, Transaction};
+use structopt::StructOpt;
+
+#[derive(StructOpt, Debug)]
+#[structopt(
+    name = "gst-log-to-sqlite",
+    about = "Import a GStreamer log into a SQLite database"
+)]
+struct Opt {
+    #[structopt(parse(from_os_str), help = "Input file, or '-' for stdin")]
+    input: PathBuf,
+    #[structopt(parse(from_os_str), help = "Output database")]
+    output: PathBuf,
+    #[structopt(
+        short = "a",
+        long = "append",
+        help = "Add the entries to an existing database rather than refusing to overwrite it"
+    )]
+    append: bool,
+    #[structopt(
+        short = "b",
+        long = "batch-size",
+        default_value = "50000",
+        help = "Number of entries inserted in each transaction"
+    )]
+    batch_size: usize,
+    #[structopt(
+        long = "filter",
+        help = "Only process entries matching this filter, such as 'level<=WARN && category~v4l2*' or a GST_DEBUG list"
+    )]
+    filter: Option<Filter>,
+}
+
+const SCHEMA: &str = "
+CREATE TABLE IF NOT EXISTS entries (
+    id INTEGER PRIMARY KEY,
+    ts INTEGER,
+    wallclock INTEGER,
+    pid INTEGER NOT NULL,
+    thread TEXT NOT NULL,
+    level TEXT NOT NULL,
+    category TEXT NOT NULL,
+    file TEXT NOT NULL,
+    line INTEGER NOT NULL,
+    function TEXT NOT NULL,
+    object TEXT,
+    element TEXT,
+    pad TEXT,
+    message TEXT NOT NULL
+);
+CREATE TABLE IF NOT EXISTS fields (
+    entry_id INTEGER NOT NULL REFERENCES entries(id),
+    structure TEXT NOT NULL,
+    name TEXT NOT NULL,
+    type TEXT,
+    value
+);
+";
+
+// Created once the import is done as maintaining them while inserting is much slower
+const INDEXES: &str = "
+CREATE INDEX IF NOT EXISTS entries_ts ON entries(ts);
+CREATE INDEX IF NOT EXISTS entries_pid ON entries(pid);
+CREATE INDEX IF NOT EXISTS entries_thread ON entries(thread);
+CREATE INDEX IF NOT EXISTS entries_level ON entries(level);
+CREATE INDEX IF NOT EXISTS entries_category ON entries(category);
+CREATE INDEX IF NOT EXISTS entries_object ON entries(object);
+CREATE INDEX IF NOT EXISTS fields_entry ON fields(entry_id);
+CREATE INDEX IF NOT EXISTS fields_structure ON fields(structure, name);
+";
+
+// Store numbers using SQLite's own types so they can be compared and aggregated.
+// SQLite integers are signed so larger unsigned values, such as GST_CLOCK_TIME_NONE, are
+// stored as text to be kept exactly.
+fn sql_value(value: &Value) -> SqlValue {
+    match value {
+        Value::Bool(v) => SqlValue::Integer(i64::from(*v)),
+        Value::Int(v) => SqlValue::Integer(i64::from(*v)),
+        Value::UInt(v) => SqlValue::Integer(i64::from(*v)),
+        Value::Int64(v) => SqlValue::Integer(*v),
+        Value::UInt64(v) => match i64::try_from(*v) {
+            Ok(v) => SqlValue::Integer(v),
+            Err(_) => SqlValue::Text(v.to_string()),
+        },
+        Value::Float(v) => SqlValue::Real(f64::from(*v)),
+        Value::Double(v) => SqlValue::Real(*v),
+        Value::String(s) => SqlValue::Text(s.clone()),
+        _ => SqlValue::Text(value.to_string()),
+    }
+}
+
+fn insert(tx: &Transaction, entry: &Entry) -> Result<(), Error> {
+    let log_object = entry.log_object();
+
+    tx.prepare_cached(
+        "INSERT INTO entries (ts, wallclock, pid, thread, level, category, file, line, function, object, element, pad, message)
+         VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9, ?10, ?11, ?12, ?13)",
+    )?
+    .execute(params![
+        entry.ts.nanoseconds().and_then(|ts| i64::try_from(ts).ok()),
+        entry
+            .wallclock
+            .and_then(|time| time.duration_since(UNIX_EPOCH).ok())
+            .map(|d| d.as_nanos() as i64),
+        entry.pid,
+        entry.thread,
+        entry.level.as_str(),
+        entry.category,
+        entry.file,
+        entry.line,
+        entry.function,
+        entry.object,
+        log_object.and_then(|o| o.element()),
+        log_object.and_then(|o| o.pad()),
+        entry.message,
+    ])?;
+
+    // Only tracers are expected to log structures, don't waste time trying to parse other messages
+    if entry.category != "GST_TRACER" {
+        return Ok(());
+    }
+
+    let s = match entry.message_structure() {
+        Some(s) => s,
+        None => return Ok(()),
+    };
+
+    let entry_id = tx.last_insert_rowid();
+    let mut stmt = tx.prepare_cached(
+        "INSERT INTO fields (entry_id, structure, name, type, value) VALUES (?1, ?2, ?3, ?4, ?5)",
+    )?;
+    for (name, value) in s.fields() {
+        stmt.execute(params![
+            entry_id,
+            s.get_name(),
+            name,
+            value.type_name(),
+            sql_value(value)
+        ])?;
+    }
+
+    Ok(())
+}
+
+// Insert @entries into the database, @batch_size entries per transaction, returning how many
+// have been inserted
+fn import<I: Iterator<Item = Entry>>(
+    conn: &mut Connection,
+    entries: I,
+    batch_size: usize,
+) -> Result<usize, Error> {
+    conn.execute_batch(SCHEMA)?;
+
+    let mut entries = entries.peekable();
+    let mut count = 0;
+    while entries.peek().is_some() {
+        let tx = conn.transaction()?;
+        for entry in entries.by_ref().take(batch_size.max(1)) {
+            insert(&tx, &entry)?;
+            count += 1;
+        }
+        tx.commit()?;
+    }
+
+    conn.execute_batch(INDEXES)?;
+    Ok(count)
+}
+
+fn main() -> Result<(), Error> {
+    let opt = Opt::from_args();
+    let input = open_log(opt.input)?;
+
+    let created = !opt.output.exists();
+    // Importing the same log twice would duplicate its entries
+    if !created && !opt.append {
+        return Err(format_err!(
+            "{} already exists, use --append to add entries to it",
+            opt.output.display()
+        ));
+    }
+    let mut conn = Connection::open(opt.output)?;
+    // A new database can be generated again from the log if the import fails, but existing
+    // data would be lost
+    if created {
+        conn.execute_batch("PRAGMA journal_mode = OFF; PRAGMA synchronous = OFF;")?;
+    }
+
+    let filter = opt.filter.unwrap_or_default();
+    let parsed = parse(input).filter(|entry| filter.matches(entry));
+    let count = import(&mut conn, parsed, opt.batch_size)?;
+    println!("Imported {} entries", count);
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::fs;
+    use std::fs::File;
+
+    fn query<T: rusqlite::types::FromSql>(conn: &Connection, sql: &str) -> T {
+        conn.query_row(sql, [], |row| row.get(0)).unwrap()
+    }
+
+    #[test]
+    fn import_test_logs() {
+        for path in fs::read_dir("test-logs").unwrap() {
+            let path = path.unwrap().path();
+            if path.extension() != Some("log".as_ref()) {
+                continue;
+            }
+
+            let f = File::open(&path).expect("Failed to open log file");
+            let expected = parse(f).count();
+            assert!(expected > 0);
+
+            let mut conn = Connection::open_in_memory().unwrap();
+            let f = File::open(&path).expect("Failed to open log file");
+            // Several transactions
+            let count = import(&mut conn, parse(f), 2).unwrap();
+            assert_eq!(count, expected);
+            let rows: i64 = query(&conn, "SELECT COUNT(*) FROM entries");
+            assert_eq!(rows as usize, expected);
+        }
+    }
+
+    #[test]
+    fn tracer_fields() {
+        let f = File::open("test-logs/tracers.log").expect("Failed to open log file");
+        let mut conn = Connection::open_in_memory().unwrap();
+        import(&mut conn, parse(f), 100).unwrap();
+
+        let fields: i64 = query(&conn, "SELECT COUNT(*) FROM fields");
+        assert_eq!(fields, 6 + 4 + 10);
+
+        let time: i64 = query(
+            &conn,
+            "SELECT value FROM fields WHERE structure = 'latency' AND name = 'time'",
+        );
+        assert_eq!(time, 4297);
+        let type_name: String = query(
+            &conn,
+            "SELECT type FROM fields WHERE structure = 'latency' AND name = 'time'",
+        );
+        assert_eq!(type_name, "guint64");
+
+        // Too large for an INTEGER
+        let dts: String = query(
+            &conn,
+            "SELECT value FROM fields WHERE structure = 'buffer' AND name = 'buffer-dts'",
+        );
+        assert_eq!(dts, "18446744073709551615");
+
+        let pad: i64 = query(
+            &conn,
+            "SELECT entry_id FROM fields WHERE structure = 'new-pad' AND name = 'name'",
+        );
+        let message: String = query(
+            &conn,
+            &format!("SELECT message FROM entries WHERE id = {}", pad),
+        );
+        assert!(message.starts_with("new-pad, ix=(uint)3"));
+    }
+}
@@ -0,0 +1,885 @@
+use std::collections::{BTreeMap, BTreeSet};
+use std::io;
+use std::panic;
+use std::path::PathBuf;
+use std::time::Duration;
+
+use crossterm::cursor;
+use crossterm::event::{self, Event, KeyCode, KeyEvent, KeyModifiers};
+use crossterm::execute;
+use crossterm::terminal::{
+    disable_raw_mode, enable_raw_mode, EnterAlternateScreen, LeaveAlternateScreen,
+};
+use failure::Error;
+use gst_log_parser::{ClockTime, DebugLevel, Entry, Filter, LogFile};
+use regex::Regex;
+use structopt::StructOpt;
+use tui::backend::{Backend, CrosstermBackend};
+use tui::layout::{Constraint, Direction, Layout, Rect};
+use tui::style::{Color, Modifier, Style};
+use tui::text::{Span, Spans};
+use tui::widgets::{Block, Borders, Cell, Paragraph, Row, Table, TableState, Wrap};
+use tui::{Frame, Terminal};
+
+#[derive(StructOpt, Debug)]
+#[structopt(
+    name = "gst-log-viewer",
+    about = "Browse a GStreamer log file interactively"
+)]
+struct Opt {
+    #[structopt(parse(from_os_str), help = "Input file")]
+    input: PathBuf,
+    #[structopt(
+        long = "filter",
+        help = "Only process entries matching this filter, such as 'level<=WARN && category~v4l2*' or a GST_DEBUG list"
+    )]
+    filter: Option<Filter>,
+}
+
+const HELP: &str = "q quit  / filter  r regex  t jump to time  b bookmark  n/N next/previous bookmark  l lanes  s side panel";
+
+// Maximum number of bytes parsed at once when looking for entries matching the filter, so the
+// screen is updated and keys are handled while scanning large logs
+const SCAN_STEP: u64 = 8 * 1024 * 1024;
+
+// Maximum number of lanes, threads beyond it share the last one
+const MAX_LANES: usize = 8;
+const LANE_COLORS: [Color; MAX_LANES] = [
+    Color::Cyan,
+    Color::Magenta,
+    Color::Yellow,
+    Color::Green,
+    Color::Blue,
+    Color::Red,
+    Color::LightCyan,
+    Color::LightMagenta,
+];
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum Prompt {
+    Filter,
+    Regex,
+    Jump,
+}
+
+// Move which could not be completed as the log has not been scanned far enough yet
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum Pending {
+    Down(usize),
+    Up(usize),
+    End,
+    KeepPosition,
+    Seek(u64, ClockTime),
+}
+
+impl Prompt {
+    fn label(self) -> &'static str {
+        match self {
+            Prompt::Filter => "filter: ",
+            Prompt::Regex => "regex: ",
+            Prompt::Jump => "time: ",
+        }
+    }
+}
+
+// Entries matching the current filter, in the parts of the log which have already been parsed,
+// so the log is parsed only once when looking for rare entries
+#[derive(Default)]
+struct Scan {
+    // Start and end offsets of the parts of the log whose entries have all been checked
+    ranges: BTreeMap<u64, u64>,
+    matches: BTreeSet<u64>,
+}
+
+impl Scan {
+    // The checked part containing @offset
+    fn containing(&self, offset: u64) -> Option<(u64, u64)> {
+        match self.ranges.range(..=offset).next_back() {
+            Some((start, end)) if offset < *end => Some((*start, *end)),
+            _ => None,
+        }
+    }
+
+    // The checked part ending at, or containing, the byte preceding @offset
+    fn preceding(&self, offset: u64) -> Option<(u64, u64)> {
+        match self.ranges.range(..offset).next_back() {
+            Some((start, end)) if offset <= *end => Some((*start, *end)),
+            _ => None,
+        }
+    }
+
+    fn checked(&mut self, mut start: u64, mut end: u64) {
+        if let Some((s, e)) = self.ranges.range(..=start).next_back() {
+            if *e >= start {
+                start = *s;
+                end = end.max(*e);
+            }
+        }
+        let merged: Vec<u64> = self.ranges.range(start..=end).map(|(s, _)| *s).collect();
+        for s in merged {
+            end = end.max(self.ranges.remove(&s).unwrap());
+        }
+        self.ranges.insert(start, end);
+    }
+}
+
+struct App {
+    log: LogFile,
+    // Offset of the first entry displayed
+    top: u64,
+    // Offset of the selected entry
+    selected: Option<u64>,
+    // Entries currently displayed, with their offset
+    rows: Vec<(u64, Entry)>,
+    // Number of entries which can be displayed
+    height: usize,
+    filter: Filter,
+    filter_text: String,
+    regex: Option<Regex>,
+    scan: Scan,
+    // Whether the last scan has been interrupted before finding all the entries
+    scanning: bool,
+    pending: Option<Pending>,
+    // Offset reached by the last interrupted scan
+    progress: u64,
+    // Prompt being edited, with its current input
+    prompt: Option<(Prompt, String)>,
+    bookmarks: BTreeSet<u64>,
+    lanes: bool,
+    // Threads in the order they have been displayed, defining their lane
+    threads: Vec<String>,
+    panel: bool,
+    status: String,
+}
+
+impl App {
+    fn new(log: LogFile, filter: Option<Filter>) -> App {
+        let filter_text = if filter.is_some() {
+            "(command line)".to_string()
+        } else {
+            String::new()
+        };
+
+        let mut app = App {
+            log,
+            top: 0,
+            selected: None,
+            rows: Vec::new(),
+            height: 0,
+            filter: filter.unwrap_or_default(),
+            filter_text,
+            regex: None,
+            scan: Scan::default(),
+            scanning: false,
+            pending: None,
+            progress: 0,
+            prompt: None,
+            bookmarks: BTreeSet::new(),
+            lanes: false,
+            threads: Vec::new(),
+            panel: false,
+            status: HELP.to_string(),
+        };
+        app.home();
+        app
+    }
+
+    fn matches(&self, entry: &Entry) -> bool {
+        let regex = match &self.regex {
+            Some(regex) => regex.is_match(&entry.message),
+            None => true,
+        };
+        regex && self.filter.matches(entry)
+    }
+
+    fn filtered(&self) -> bool {
+        !self.filter_text.is_empty() || self.regex.is_some()
+    }
+
+    // Offsets of the first @n matching entries starting at, or after, @offset.
+    // Fewer entries may be returned if the scan has been interrupted, see `scanning`.
+    fn forward(&mut self, mut offset: u64, n: usize) -> Vec<u64> {
+        self.scanning = false;
+        if !self.filtered() {
+            return self
+                .log
+                .entries_from(offset)
+                .take(n)
+                .map(|(offset, _)| offset)
+                .collect();
+        }
+
+        let mut found = Vec::new();
+        let mut scanned = 0;
+        while found.len() < n {
+            if let Some((_, end)) = self.scan.containing(offset) {
+                let matches = self.scan.matches.range(offset..end);
+                found.extend(matches.take(n - found.len()));
+                offset = end;
+                continue;
+            }
+            if scanned >= SCAN_STEP {
+                self.scanning = true;
+                self.progress = offset;
+                break;
+            }
+
+            let start = match self.log.next_entry(offset) {
+                Some(start) => start,
+                None => {
+                    self.scan.checked(offset, self.log.len());
+                    break;
+                }
+            };
+            let end = match self.log.entry_at(start) {
+                Some((entry, end)) => {
+                    if self.matches(&entry) {
+                        self.scan.matches.insert(start);
+                        found.push(start);
+                    }
+                    end
+                }
+                // Not an entry which can be parsed
+                None => start + 1,
+            };
+            self.scan.checked(offset, end);
+            scanned += end - offset;
+            offset = end;
+        }
+
+        found
+    }
+
+    // Offsets of the @n closest matching entries starting before @offset.
+    // Fewer entries may be returned if the scan has been interrupted, see `scanning`.
+    fn backward(&mut self, mut offset: u64, n: usize) -> Vec<u64> {
+        self.scanning = false;
+        if !self.filtered() {
+            return self
+                .log
+                .entries_before(offset)
+                .take(n)
+                .map(|(offset, _)| offset)
+                .collect();
+        }
+
+        let mut found = Vec::new();
+        let mut scanned = 0;
+        while found.len() < n && offset > 0 {
+            if let Some((start, _)) = self.scan.preceding(offset) {
+                let matches = self.scan.matches.range(start..offset).rev();
+                found.extend(matches.take(n - found.len()));
+                offset = start;
+                continue;
+            }
+            if scanned >= SCAN_STEP {
+                self.scanning = true;
+                self.progress = offset;
+                break;
+            }
+
+            let start = match self.log.previous_entry(offset) {
+                Some(start) => start,
+                None => {
+                    self.scan.checked(0, offset);
+                    break;
+                }
+            };
+            if let Some((entry, _)) = self.log.entry_at(start) {
+                if self.matches(&entry) {
+                    self.scan.matches.insert(start);
+                    found.push(start);
+                }
+            }
+            self.scan.checked(start, offset);
+            scanned += offset - start;
+            offset = start;
+        }
+
+        found
+    }
+
+    // Re-read the displayed entries, as many as @height
+    fn refresh(&mut self, height: usize) {
+        self.height = height.max(1);
+        let offsets = self.forward(self.top, self.height);
+        self.rows = offsets
+            .into_iter()
+            .filter_map(|offset| self.log.entry_at(offset).map(|(entry, _)| (offset, entry)))
+            .collect();
+
+        if let Some((offset, _)) = self.rows.first() {
+            self.top = *offset;
+        }
+        let selected_shown = match self.selected {
+            Some(selected) => self.rows.iter().any(|(offset, _)| *offset == selected),
+            None => false,
+        };
+        if !selected_shown {
+            self.selected = self.rows.first().map(|(offset, _)| *offset);
+        }
+
+        for (_, entry) in &self.rows {
+            if !self.threads.contains(&entry.thread) {
+                self.threads.push(entry.thread.clone());
+            }
+        }
+    }
+
+    fn lane(&self, thread: &str) -> usize {
+        let lane = self.threads.iter().position(|t| t == thread).unwrap_or(0);
+        lane.min(MAX_LANES - 1)
+    }
+
+    fn selected_entry(&self) -> Option<&Entry> {
+        let selected = self.selected?;
+        self.rows
+            .iter()
+            .find(|(offset, _)| *offset == selected)
+            .map(|(_, entry)| entry)
+    }
+
+    // Select the entry at @offset, scrolling so it's displayed
+    fn select(&mut self, offset: u64) {
+        self.selected = Some(offset);
+
+        let shown = match self.rows.last() {
+            Some((last, _)) => offset <= *last,
+            None => false,
+        };
+        if offset < self.top {
+            self.top = offset;
+        } else if !shown {
+            // Display the entry at the bottom of the screen
+            let above = self.backward(offset, self.height.saturating_sub(1));
+            self.top = above.last().cloned().unwrap_or(offset);
+        }
+    }
+
+    fn down(&mut self, n: usize) {
+        if let Some(selected) = self.selected {
+            let found = self.forward(selected + 1, n);
+            if self.scanning {
+                self.pending = Some(Pending::Down(n));
+            } else if let Some(offset) = found.last() {
+                self.select(*offset);
+            }
+        }
+    }
+
+    fn up(&mut self, n: usize) {
+        if let Some(selected) = self.selected {
+            let found = self.backward(selected, n);
+            if self.scanning {
+                self.pending = Some(Pending::Up(n));
+            } else if let Some(offset) = found.last() {
+                self.select(*offset);
+            }
+        }
+    }
+
+    fn home(&mut self) {
+        self.top = 0;
+        self.selected = None;
+    }
+
+    fn end(&mut self) {
+        let len = self.log.len();
+        let found = self.backward(len, 1);
+        if self.scanning {
+            self.pending = Some(Pending::End);
+        } else if let Some(offset) = found.first() {
+            self.select(*offset);
+        }
+    }
+
+    // Display the first matching entry at, or after, @offset at the top of the screen.
+    // Returns false if there is none, or if the log has not been scanned far enough yet.
+    fn jump(&mut self, offset: u64) -> bool {
+        match self.forward(offset, 1).first() {
+            Some(offset) => {
+                self.top = *offset;
+                self.selected = Some(*offset);
+                true
+            }
+            None => false,
+        }
+    }
+
+    fn seek(&mut self, offset: u64, ts: ClockTime) {
+        if !self.jump(offset) {
+            if self.scanning {
+                self.pending = Some(Pending::Seek(offset, ts));
+            } else {
+                self.status = format!("No entry after {}", ts);
+            }
+        }
+    }
+
+    fn toggle_bookmark(&mut self) {
+        if let Some(selected) = self.selected {
+            if !self.bookmarks.remove(&selected) {
+                self.bookmarks.insert(selected);
+            }
+        }
+    }
+
+    // Select the closest matching bookmark after, or before, the selected entry
+    fn bookmark(&mut self, next: bool) {
+        let selected = match self.selected {
+            Some(selected) => selected,
+            None => return,
+        };
+        let matching = |offset: &&u64| match self.log.entry_at(**offset) {
+            Some((entry, _)) => self.matches(&entry),
+            None => false,
+        };
+        let found = if next {
+            self.bookmarks.range(selected + 1..).find(matching)
+        } else {
+            self.bookmarks.range(..selected).rev().find(matching)
+        };
+
+        match found.cloned() {
+            Some(offset) => self.select(offset),
+            None => self.status = "No more bookmarks".to_string(),
+        }
+    }
+
+    fn apply_prompt(&mut self, prompt: Prompt, input: String) {
+        match prompt {
+            Prompt::Filter => match Filter::parse(&input) {
+                Ok(filter) => {
+                    self.filter = filter;
+                    self.filter_text = input;
+                    self.scan = Scan::default();
+                    self.keep_position();
+                }
+                Err(err) => self.status = format!("Invalid filter: {}", err),
+            },
+            Prompt::Regex if input.is_empty() => {
+                self.regex = None;
+                self.scan = Scan::default();
+                self.keep_position();
+            }
+            Prompt::Regex => match Regex::new(&input) {
+                Ok(regex) => {
+                    self.regex = Some(regex);
+                    self.scan = Scan::default();
+                    self.keep_position();
+                }
+                Err(err) => self.status = format!("Invalid regex: {}", err),
+            },
+            Prompt::Jump => match input.parse::<ClockTime>() {
+                Ok(ts) => match self.log.seek(ts) {
+                    Ok(Some(offset)) => self.seek(offset, ts),
+                    Ok(None) => self.status = format!("No entry after {}", ts),
+                    Err(err) => self.status = format!("Failed to index the log: {}", err),
+                },
+                Err(_) => self.status = format!("Invalid time '{}'", input),
+            },
+        }
+    }
+
+    // Stay around the selected entry once the displayed entries have changed
+    fn keep_position(&mut self) {
+        let position = self.selected.unwrap_or(self.top);
+        let found = self.jump(position) || (!self.scanning && self.jump(0));
+        if self.scanning {
+            self.pending = Some(Pending::KeepPosition);
+        } else if !found {
+            self.status = "No entry matching".to_string();
+        }
+    }
+
+    // Carry on with the move which could not be completed, scanning the next part of the log
+    fn resume(&mut self) {
+        match self.pending.take() {
+            Some(Pending::Down(n)) => self.down(n),
+            Some(Pending::Up(n)) => self.up(n),
+            Some(Pending::End) => self.end(),
+            Some(Pending::KeepPosition) => self.keep_position(),
+            Some(Pending::Seek(offset, ts)) => self.seek(offset, ts),
+            None => {}
+        }
+    }
+
+    // Still looking for entries matching the filter
+    fn is_scanning(&self) -> bool {
+        self.pending.is_some() || self.scanning
+    }
+
+    // Handle a key press, returning false to quit
+    fn key(&mut self, key: KeyEvent) -> bool {
+        // Any key cancels the move in progress
+        self.pending = None;
+
+        if let Some((prompt, mut input)) = self.prompt.take() {
+            match key.code {
+                KeyCode::Enter => self.apply_prompt(prompt, input),
+                KeyCode::Esc => {}
+                KeyCode::Backspace => {
+                    input.pop();
+                    self.prompt = Some((prompt, input));
+                }
+                KeyCode::Char(c) => {
+                    input.push(c);
+                    self.prompt = Some((prompt, input));
+                }
+                _ => self.prompt = Some((prompt, input)),
+            }
+            return true;
+        }
+
+        self.status = HELP.to_string();
+        let page = self.height.saturating_sub(1).max(1);
+        match key.code {
+            KeyCode::Char('q') => return false,
+            KeyCode::Char('c') if key.modifiers.contains(KeyModifiers::CONTROL) => return false,
+            KeyCode::Down | KeyCode::Char('j') => self.down(1),
+            KeyCode::Up | KeyCode::Char('k') => self.up(1),
+            KeyCode::PageDown | KeyCode::Char(' ') => self.down(page),
+            KeyCode::PageUp => self.up(page),
+            KeyCode::Home | KeyCode::Char('g') => self.home(),
+            KeyCode::End | KeyCode::Char('G') => self.end(),
+            KeyCode::Char('/') => self.prompt = Some((Prompt::Filter, self.filter_text.clone())),
+            KeyCode::Char('r') => {
+                let regex = self.regex.as_ref().map(|r| r.as_str().to_string());
+                self.prompt = Some((Prompt::Regex, regex.unwrap_or_default()));
+            }
+            KeyCode::Char('t') => self.prompt = Some((Prompt::Jump, String::new())),
+            KeyCode::Char('b') => self.toggle_bookmark(),
+            KeyCode::Char('n') => self.bookmark(true),
+            KeyCode::Char('N') => self.bookmark(false),
+            KeyCode::Char('l') => self.lanes = !self.lanes,
+            KeyCode::Char('s') => self.panel = !self.panel,
+            _ => {}
+        }
+
+        true
+    }
+}
+
+fn level_style(level: DebugLevel) -> Style {
+    let style = Style::default();
+    match level {
+        DebugLevel::Error => style.fg(Color::Red).add_modifier(Modifier::BOLD),
+        DebugLevel::Warning => style.fg(Color::Yellow),
+        DebugLevel::Fixme => style.fg(Color::Green),
+        DebugLevel::Info => style.fg(Color::Cyan),
+        DebugLevel::Debug => style,
+        _ => style.fg(Color::DarkGray),
+    }
+}
+
+// Vertical line for each lane with a mark in the one of @lane
+fn lanes_cell(lane: usize, count: usize) -> Spans<'static> {
+    let spans: Vec<Span> = (0..count)
+        .map(|i| {
+            let style = Style::default().fg(LANE_COLORS[i]);
+            if i == lane {
+                Span::styled("● ", style.add_modifier(Modifier::BOLD))
+            } else {
+                Span::styled("│ ", style)
+            }
+        })
+        .collect();
+    Spans::from(spans)
+}
+
+fn draw_entries<B: Backend>(f: &mut Frame<B>, app: &App, area: Rect) {
+    let lanes = app.threads.len().min(MAX_LANES);
+    let rows = app.rows.iter().map(|(offset, entry)| {
+        let mark = if app.bookmarks.contains(offset) {
+            "*"
+        } else {
+            " "
+        };
+        let mut cells = vec![
+            Cell::from(mark),
+            Cell::from(entry.ts.to_string()),
+            Cell::from(entry.thread.as_str()),
+            Cell::from(entry.level.as_str()).style(level_style(entry.level)),
+            Cell::from(entry.category.as_str()),
+        ];
+        if app.lanes {
+            cells.push(Cell::from(lanes_cell(app.lane(&entry.thread), lanes)));
+        }
+        let message = match &entry.object {
+            Some(object) => format!("<{}> {}", object, entry.message),
+            None => entry.message.clone(),
+        };
+        cells.push(Cell::from(message));
+
+        Row::new(cells).style(level_style(entry.level))
+    });
+
+    let mut header = vec!["", "time", "thread", "level", "category"];
+    let mut widths = vec![
+        Constraint::Length(1),
+        Constraint::Length(18),
+        Constraint::Length(14),
+        Constraint::Length(7),
+        Constraint::Length(16),
+    ];
+    if app.lanes {
+        header.push("lanes");
+        widths.push(Constraint::Length(lanes.max(3) as u16 * 2));
+    }
+    header.push("message");
+    widths.push(Constraint::Min(10));
+
+    let title = if app.filter_text.is_empty() && app.regex.is_none() {
+        "gst-log-viewer".to_string()
+    } else {
+        let regex = app.regex.as_ref().map(|r| r.as_str()).unwrap_or("");
+        format!("filter: {}  regex: {}", app.filter_text, regex)
+    };
+    let table = Table::new(rows)
+        .header(Row::new(header).style(Style::default().add_modifier(Modifier::BOLD)))
+        .block(Block::default().borders(Borders::ALL).title(title))
+        .widths(&widths)
+        .column_spacing(1)
+        .highlight_style(Style::default().add_modifier(Modifier::REVERSED));
+
+    let mut state = TableState::default();
+    state.select(
+        app.selected
+            .and_then(|selected| app.rows.iter().position(|(offset, _)| *offset == selected)),
+    );
+    f.render_stateful_widget(table, area, &mut state);
+}
+
+fn field(name: &str, value: String) -> Spans<'static> {
+    Spans::from(vec![
+        Span::styled(
+            format!("{}: ", name),
+            Style::default().add_modifier(Modifier::BOLD),
+        ),
+        Span::raw(value),
+    ])
+}
+
+// Details of the selected entry, with the fields of its message if it's a structure
+fn draw_panel<B: Backend>(f: &mut Frame<B>, app: &App, area: Rect) {
+    let mut text = Vec::new();
+
+    if let Some(entry) = app.selected_entry() {
+        text.push(field("time", entry.ts.to_string()));
+        text.push(field("pid", entry.pid.to_string()));
+        text.push(field("thread", entry.thread.clone()));
+        text.push(field("level", entry.level.to_string()));
+        text.push(field("category", entry.category.clone()));
+        text.push(field(
+            "location",
+            format!("{}:{}:{}", entry.file, entry.line, entry.function),
+        ));
+        if let Some(object) = &entry.object {
+            text.push(field("object", object.clone()));
+        }
+        text.push(Spans::default());
+
+        match entry.message_structure() {
+            Some(structure) => {
+                text.push(Spans::from(Span::styled(
+                    structure.get_name().to_string(),
+                    Style::default().add_modifier(Modifier::BOLD),
+                )));
+                for (name, value) in structure.fields() {
+                    let value = match value.type_name() {
+                        Some(type_name) => format!("({}) {}", type_name, value),
+                        None => value.to_string(),
+                    };
+                    text.push(field(&format!("  {}", name), value));
+                }
+            }
+            None => {
+                for line in entry.message.lines() {
+                    text.push(Spans::from(line.to_string()));
+                }
+            }
+        }
+    }
+
+    let panel = Paragraph::new(text)
+        .block(Block::default().borders(Borders::ALL).title("entry"))
+        .wrap(Wrap { trim: false });
+    f.render_widget(panel, area);
+}
+
+fn draw<B: Backend>(f: &mut Frame<B>, app: &App, area: Rect) {
+    let chunks = Layout::default()
+        .direction(Direction::Vertical)
+        .constraints([Constraint::Min(3), Constraint::Length(1)].as_ref())
+        .split(area);
+
+    if app.panel {
+        let columns = Layout::default()
+            .direction(Direction::Horizontal)
+            .constraints([Constraint::Percentage(65), Constraint::Percentage(35)].as_ref())
+            .split(chunks[0]);
+        draw_entries(f, app, columns[0]);
+        draw_panel(f, app, columns[1]);
+    } else {
+        draw_entries(f, app, chunks[0]);
+    }
+
+    let status = match &app.prompt {
+        Some((prompt, input)) => format!("{}{}", prompt.label(), input),
+        None if app.pending.is_some() => format!(
+            "Scanning the log, {}% (press any key to cancel)",
+            app.progress * 100 / app.log.len().max(1)
+        ),
+        None => app.status.clone(),
+    };
+    f.render_widget(
+        Paragraph::new(status).style(Style::default().add_modifier(Modifier::REVERSED)),
+        chunks[1],
+    );
+}
+
+fn run<B: Backend>(terminal: &mut Terminal<B>, app: &mut App) -> Result<(), Error> {
+    loop {
+        // Borders, header and status bar
+        let height = terminal.size()?.height.saturating_sub(4);
+        app.refresh(height as usize);
+        terminal.draw(|f| draw(f, app, f.size()))?;
+
+        // Scan the next part of the log unless a key has been pressed
+        if app.is_scanning() && !event::poll(Duration::from_millis(0))? {
+            app.resume();
+            continue;
+        }
+
+        if let Event::Key(key) = event::read()? {
+            if !app.key(key) {
+                return Ok(());
+            }
+        }
+    }
+}
+
+fn restore_terminal() {
+    let _ = disable_raw_mode();
+    let _ = execute!(io::stdout(), LeaveAlternateScreen, cursor::Show);
+}
+
+// Restore the terminal when leaving, including on errors and panics
+struct TerminalGuard;
+
+impl TerminalGuard {
+    fn new() -> Result<TerminalGuard, Error> {
+        // The panic message would be lost on the alternate screen otherwise
+        let hook = panic::take_hook();
+        panic::set_hook(Box::new(move |info| {
+            restore_terminal();
+            hook(info);
+        }));
+
+        enable_raw_mode()?;
+        let guard = TerminalGuard;
+        execute!(io::stdout(), EnterAlternateScreen)?;
+        Ok(guard)
+    }
+}
+
+impl Drop for TerminalGuard {
+    fn drop(&mut self) {
+        restore_terminal();
+    }
+}
+
+fn main() -> Result<(), Error> {
+    let opt = Opt::from_args();
+    let log = LogFile::open(&opt.input)?;
+    let mut app = App::new(log, opt.filter);
+
+    let _guard = TerminalGuard::new()?;
+    let mut terminal = Terminal::new(CrosstermBackend::new(io::stdout()))?;
+
+    run(&mut terminal, &mut app)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn ranges(scan: &Scan) -> Vec<(u64, u64)> {
+        scan.ranges.iter().map(|(s, e)| (*s, *e)).collect()
+    }
+
+    #[test]
+    fn checked_disjoint() {
+        let mut scan = Scan::default();
+        scan.checked(30, 40);
+        scan.checked(10, 20);
+        assert_eq!(ranges(&scan), vec![(10, 20), (30, 40)]);
+    }
+
+    #[test]
+    fn checked_adjacent() {
+        let mut scan = Scan::default();
+        scan.checked(10, 20);
+        scan.checked(20, 30);
+        assert_eq!(ranges(&scan), vec![(10, 30)]);
+        scan.checked(0, 10);
+        assert_eq!(ranges(&scan), vec![(0, 30)]);
+
+        // Filling the gap between two parts
+        scan.checked(40, 50);
+        scan.checked(30, 40);
+        assert_eq!(ranges(&scan), vec![(0, 50)]);
+    }
+
+    #[test]
+    fn checked_overlapping() {
+        let mut scan = Scan::default();
+        scan.checked(10, 20);
+        scan.checked(15, 25);
+        assert_eq!(ranges(&scan), vec![(10, 25)]);
+        scan.checked(5, 12);
+        assert_eq!(ranges(&scan), vec![(5, 25)]);
+
+        // Already checked
+        scan.checked(12, 18);
+        assert_eq!(ranges(&scan), vec![(5, 25)]);
+
+        // Covering several parts
+        scan.checked(30, 40);
+        scan.checked(50, 60);
+        assert_eq!(ranges(&scan), vec![(5, 25), (30, 40), (50, 60)]);
+        scan.checked(20, 55);
+        assert_eq!(ranges(&scan), vec![(5, 60)]);
+        scan.checked(0, 100);
+        assert_eq!(ranges(&scan), vec![(0, 100)]);
+    }
+
+    #[test]
+    fn containing() {
+        let mut scan = Scan::default();
+        scan.checked(10, 20);
+        scan.checked(30, 40);
+        assert_eq!(scan.containing(0), None);
+        assert_eq!(scan.containing(9), None);
+        assert_eq!(scan.containing(10), Some((10, 20)));
+        assert_eq!(scan.containing(19), Some((10, 20)));
+        assert_eq!(scan.containing(20), None);
+        assert_eq!(scan.containing(30), Some((30, 40)));
+        assert_eq!(scan.containing(40), None);
+    }
+
+    #[test]
+    fn preceding() {
+        let mut scan = Scan::default();
+        scan.checked(10, 20);
+        scan.checked(30, 40);
+        assert_eq!(scan.preceding(0), None);
+        assert_eq!(scan.preceding(10), None);
+        assert_eq!(scan.preceding(11), Some((10, 20)));
+        assert_eq!(scan.preceding(20), Some((10, 20)));
+        assert_eq!(scan.preceding(21), None);
+        assert_eq!(scan.preceding(30), None);
+        assert_eq!(scan.preceding(40), Some((30, 40)));
+        assert_eq!(scan.preceding(100), None);
+    }
+}
@@ -1,19 +1,22 @@
 // Generate input logs with: GST_DEBUG="GST_TRACER:7" GST_TRACERS=latency\(flags="pipeline+element+reported"\)
 
 use failure::Error;
-use gst_log_parser::parse;
-use gstreamer::{ClockTime, DebugLevel};
+use gst_log_parser::{open_log, parse, ClockTime, DebugLevel, Filter};
 use itertools::Itertools;
 use std::collections::HashMap;
-use std::fs::File;
 use std::path::PathBuf;
 use structopt::StructOpt;
 
 #[derive(StructOpt, Debug)]
 #[structopt(name = "latency")]
 struct Opt {
-    #[structopt(parse(from_os_str))]
+    #[structopt(parse(from_os_str), help = "Input file, or '-' for stdin")]
     input: PathBuf,
+    #[structopt(
+        long = "filter",
+        help = "Only process entries matching this filter, such as 'level<=WARN && category~v4l2*' or a GST_DEBUG list"
+    )]
+    filter: Option<Filter>,
 }
 
 #[derive(Debug)]
@@ -37,15 +40,17 @@ impl Count {
 
 fn main() -> Result<(), Error> {
     let opt = Opt::from_args();
-    let input = File::open(opt.input)?;
+    let input = open_log(opt.input)?;
+    let filter = opt.filter.unwrap_or_default();
 
     let mut elt_latency: HashMap<String, Count> = HashMap::new();
     let parsed = parse(input)
-        .filter(|entry| entry.category == "GST_TRACER" && entry.level == DebugLevel::Trace);
+        .filter(|entry| entry.category == "GST_TRACER" && entry.level == DebugLevel::Trace)
+        .filter(|entry| filter.matches(entry));
 
     for entry in parsed {
         let s = entry
-            .message_to_struct()
+            .message_structure()
             .expect("Failed to parse structure");
 
         match s.get_name() {
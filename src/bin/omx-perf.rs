@@ -5,10 +5,7 @@ use std::io::Write;
 use std::process::exit;
 
 extern crate gst_log_parser;
-use gst_log_parser::parse;
-
-extern crate gstreamer as gst;
-use gst::DebugLevel;
+use gst_log_parser::{can_follow, open_log, try_parse, DebugLevel, Filter};
 
 extern crate structopt;
 extern crate structopt_derive;
@@ -20,10 +17,21 @@ use structopt::StructOpt;
     about = "Generate a data file from OMX performance logs"
 )]
 struct Opt {
-    #[structopt(help = "Input file")]
+    #[structopt(help = "Input file, or '-' for stdin")]
     input: String,
     #[structopt(help = "Output file")]
     output: String,
+    #[structopt(
+        short = "f",
+        long = "follow",
+        help = "Wait for more data once the end of the input has been reached"
+    )]
+    follow: bool,
+    #[structopt(
+        long = "filter",
+        help = "Only process entries matching this filter, such as 'level<=WARN && category~v4l2*' or a GST_DEBUG list"
+    )]
+    filter: Option<Filter>,
 }
 
 struct Count {
@@ -46,22 +54,26 @@ impl Count {
 
 fn generate() -> Result<bool, std::io::Error> {
     let opt = Opt::from_args();
-    let input = File::open(opt.input)?;
+    // Pipes are over once their end has been reached
+    let follow = opt.follow && can_follow(&opt.input);
+    let input = open_log(opt.input)?;
     let mut output = (File::create(&opt.output))?;
+    let filter = opt.filter.unwrap_or_default();
 
-    let parsed = parse(input)
-        .filter(|entry| entry.category == "OMX_API_TRACE" && entry.level == DebugLevel::Trace);
+    let parsed = try_parse(input)
+        .follow(follow)
+        .filter_map(Result::ok)
+        .filter(|entry| entry.category == "OMX_API_TRACE" && entry.level == DebugLevel::Trace)
+        .filter(|entry| filter.matches(entry));
     let mut counts: HashMap<String, Count> = HashMap::new();
 
     for entry in parsed {
         println!("{}", entry);
         let s = entry
-            .message_to_struct()
+            .message_structure()
             .expect("Failed to parse structure");
-        let object = entry.object.unwrap();
-        // Extract the component name by taking the 4th last chars of the gst object name
-        if let Some((i, _)) = object.char_indices().rev().nth(3) {
-            let comp_name = &object[i..];
+        // OMX calls are logged using the element wrapping the component
+        if let Some(comp_name) = entry.log_object().and_then(|object| object.omx_component()) {
             let event = s.get_name();
             let count = counts
                 .entry(comp_name.to_string())
@@ -0,0 +1,340 @@
+// Generate input logs with: GST_DEBUG="GST_STATES:6"
+use std::collections::HashMap;
+
+use failure::Error;
+use gst_log_parser::{
+    open_log, try_parse, ClockTime, Entry, Filter, MessageKind, State, StateChangeReturn,
+};
+use structopt::StructOpt;
+
+#[derive(StructOpt)]
+#[structopt(
+    name = "states",
+    about = "Display the state changes of each element and bin from GST_STATES logs"
+)]
+struct Opt {
+    #[structopt(help = "Input file, or '-' for stdin")]
+    input: String,
+    #[structopt(
+        short = "s",
+        long = "stuck",
+        help = "Only display elements with a state change which did not complete"
+    )]
+    stuck: bool,
+    #[structopt(
+        short = "w",
+        long = "width",
+        default_value = "60",
+        help = "Width of the timeline"
+    )]
+    width: usize,
+    #[structopt(
+        long = "filter",
+        help = "Only process entries matching this filter, such as 'level<=WARN && category~v4l2*' or a GST_DEBUG list"
+    )]
+    filter: Option<Filter>,
+}
+
+// A state change requested with set_state(), and the steps it went through
+struct Transition {
+    // None if the state of the element was not known
+    from: Option<State>,
+    target: State,
+    start: ClockTime,
+    // When the target state has been reached
+    end: Option<ClockTime>,
+    // Values returned while changing state, the last one being the result of set_state()
+    returns: Vec<StateChangeReturn>,
+    // Intermediate state changes: time, old and new state
+    steps: Vec<(ClockTime, State, State)>,
+}
+
+impl Transition {
+    fn new(from: Option<State>, target: State, start: ClockTime) -> Transition {
+        Transition {
+            from,
+            target,
+            start,
+            end: None,
+            returns: Vec::new(),
+            steps: Vec::new(),
+        }
+    }
+
+    fn is_async(&self) -> bool {
+        self.returns.contains(&StateChangeReturn::Async)
+    }
+
+    fn failed(&self) -> bool {
+        self.returns.contains(&StateChangeReturn::Failure)
+    }
+
+    // An asynchronous change which never reached its target, or a failed one
+    fn stuck(&self) -> bool {
+        self.failed() || (self.is_async() && self.end.is_none())
+    }
+}
+
+#[derive(Default)]
+struct Element {
+    // Bins are recognized by the file logging their state changes
+    bin: bool,
+    current: Option<State>,
+    transitions: Vec<Transition>,
+}
+
+impl Element {
+    fn handle(&mut self, entry: &Entry) {
+        let ts = entry.ts;
+        if entry.file == "gstbin.c" {
+            self.bin = true;
+        }
+
+        match entry.message_kind() {
+            MessageKind::SetState(target) => {
+                let mut transition = Transition::new(self.current, target, ts);
+                // Nothing else is logged if the element is already in the target state
+                if self.current == Some(target) {
+                    transition.end = Some(ts);
+                }
+                self.transitions.push(transition);
+            }
+            MessageKind::StateChangeReturn(ret) => {
+                if let Some(transition) = self.transitions.last_mut() {
+                    transition.returns.push(ret);
+                }
+            }
+            MessageKind::StateChanged { old, new, pending } => {
+                self.current = Some(new);
+                // The state may have been changed by the parent bin without any set_state()
+                // logged, such as when losing state.
+                let in_progress = match self.transitions.last() {
+                    Some(transition) => transition.end.is_none(),
+                    None => false,
+                };
+                if !in_progress {
+                    let target = pending.unwrap_or(new);
+                    self.transitions
+                        .push(Transition::new(Some(old), target, ts));
+                }
+
+                let transition = self.transitions.last_mut().unwrap();
+                transition.steps.push((ts, old, new));
+                if new == transition.target && pending.is_none() && transition.end.is_none() {
+                    transition.end = Some(ts);
+                }
+            }
+            // Logged before the state change is notified, which is what ends the transition
+            MessageKind::StateCompleted(state) => self.current = Some(state),
+            _ => {}
+        }
+    }
+
+    fn stuck(&self) -> bool {
+        self.transitions.iter().any(Transition::stuck)
+    }
+
+    // The state of the element at @ts, and if a state change was in progress
+    fn state_at(&self, ts: ClockTime) -> (Option<State>, bool) {
+        let mut state = None;
+        let mut changing = false;
+
+        for transition in self.transitions.iter().take_while(|t| t.start <= ts) {
+            if state.is_none() {
+                state = transition.from;
+            }
+            for (_, _, new) in transition.steps.iter().take_while(|(t, _, _)| *t <= ts) {
+                state = Some(*new);
+            }
+            changing = match transition.end {
+                Some(end) => ts < end,
+                None => transition.target != state.unwrap_or(State::VoidPending),
+            };
+        }
+
+        (state, changing)
+    }
+}
+
+fn state_char(state: Option<State>, changing: bool) -> char {
+    if changing {
+        return '~';
+    }
+    match state {
+        None | Some(State::VoidPending) => ' ',
+        Some(State::Null) => '.',
+        Some(State::Ready) => '-',
+        Some(State::Paused) => '=',
+        Some(State::Playing) => '#',
+    }
+}
+
+fn print_element(name: &str, element: &Element) {
+    println!("{}{}", name, if element.bin { " (bin)" } else { "" });
+
+    for transition in &element.transitions {
+        let from = match transition.from {
+            Some(from) => from.to_string(),
+            None => "?".to_string(),
+        };
+        let ret = match transition.returns.last() {
+            Some(ret) => ret.to_string(),
+            None => "".to_string(),
+        };
+        let duration = match transition.end {
+            Some(end) => (end - transition.start).to_string(),
+            None => "not completed".to_string(),
+        };
+        let flag = if transition.stuck() { " !" } else { "" };
+
+        println!(
+            "  {} {:>12} -> {:<12} {:<11} {}{}",
+            transition.start,
+            from,
+            transition.target.to_string(),
+            ret,
+            duration,
+            flag
+        );
+        for (ts, old, new) in &transition.steps {
+            println!("      {} {} -> {}", ts, old, new);
+        }
+    }
+}
+
+fn main() -> Result<(), Error> {
+    let opt = Opt::from_args();
+    let input = open_log(&opt.input)?;
+    let filter = opt.filter.clone().unwrap_or_default();
+
+    // Elements in the order they appear in the log
+    let mut elements: Vec<(String, Element)> = Vec::new();
+    let mut indexes: HashMap<String, usize> = HashMap::new();
+    let (mut first, mut last) = (ClockTime::none(), ClockTime::none());
+
+    let parsed = try_parse(input)
+        .filter_map(Result::ok)
+        .filter(|entry| filter.matches(entry));
+    for entry in parsed {
+        let object = match &entry.object {
+            Some(object) => object,
+            None => continue,
+        };
+        match entry.message_kind() {
+            MessageKind::SetState(_)
+            | MessageKind::StateChangeReturn(_)
+            | MessageKind::StateChanged { .. }
+            | MessageKind::StateCompleted(_) => {}
+            // Used to recognize bins
+            _ if entry.file == "gstbin.c" => {}
+            _ => continue,
+        }
+
+        let index = *indexes.entry(object.clone()).or_insert_with(|| {
+            elements.push((object.clone(), Element::default()));
+            elements.len() - 1
+        });
+        elements[index].1.handle(&entry);
+
+        if first.is_none() {
+            first = entry.ts;
+        }
+        last = entry.ts;
+    }
+
+    let elements: Vec<&(String, Element)> = elements
+        .iter()
+        .filter(|(_, element)| !opt.stuck || element.stuck())
+        .collect();
+
+    for (name, element) in &elements {
+        print_element(name, element);
+        println!();
+    }
+
+    // Timeline of the state of each element, one column per period of time
+    let (first, last) = match (first.nanoseconds(), last.nanoseconds()) {
+        (Some(first), Some(last)) if last > first && opt.width > 0 => (first, last),
+        _ => return Ok(()),
+    };
+    let width = opt.width as u64;
+    let name_width = elements
+        .iter()
+        .map(|(name, _)| name.len())
+        .max()
+        .unwrap_or(0);
+
+    println!(
+        "Timeline from {} to {} ('.' NULL, '-' READY, '=' PAUSED, '#' PLAYING, '~' changing, '!' not completed)",
+        ClockTime::from_nseconds(first),
+        ClockTime::from_nseconds(last)
+    );
+    for (name, element) in &elements {
+        let timeline: String = (0..width)
+            .map(|column| {
+                let ts =
+                    ClockTime::from_nseconds(first + (last - first) * column / (width - 1).max(1));
+                let (state, changing) = element.state_at(ts);
+                state_char(state, changing)
+            })
+            .collect();
+        let flag = if element.stuck() { " !" } else { "" };
+        println!(
+            "  {:<width$} |{}|{}",
+            name,
+            timeline,
+            flag,
+            width = name_width
+        );
+    }
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use gst_log_parser::parse;
+
+    fn element(log: &str) -> Element {
+        let mut element = Element::default();
+        for entry in parse(log.as_bytes()) {
+            element.handle(&entry);
+        }
+        element
+    }
+
+    #[test]
+    fn transitions() {
+        let element = element(
+            "0:00:00.100000000 1 0x1 DEBUG GST_STATES gstelement.c:2849:gst_element_set_state_func:<src> set_state to PAUSED
+0:00:00.100100000 1 0x1 INFO GST_STATES gstelement.c:2726:gst_element_continue_state:<src> completed state change to PAUSED
+0:00:00.100200000 1 0x1 INFO GST_STATES gstelement.c:2626:_priv_gst_element_state_changed:<src> notifying about state-changed READY to PAUSED (VOID_PENDING pending)
+0:00:00.200000000 1 0x1 DEBUG GST_STATES gstelement.c:2849:gst_element_set_state_func:<src> set_state to PAUSED
+0:00:00.300000000 1 0x1 INFO GST_STATES gstelement.c:2626:_priv_gst_element_state_changed:<src> notifying about state-changed PAUSED to READY (VOID_PENDING pending)
+",
+        );
+
+        assert_eq!(element.transitions.len(), 3);
+
+        let transition = &element.transitions[0];
+        assert_eq!(transition.from, None);
+        assert_eq!(transition.target, State::Paused);
+        assert_eq!(transition.end, Some(ClockTime::from_nseconds(100_200_000)));
+        assert_eq!(transition.steps.len(), 1);
+
+        // Already in the target state
+        let transition = &element.transitions[1];
+        assert_eq!(transition.from, Some(State::Paused));
+        assert_eq!(transition.end, Some(transition.start));
+        assert!(transition.steps.is_empty());
+
+        // Not requested with set_state()
+        let transition = &element.transitions[2];
+        assert_eq!(transition.from, Some(State::Paused));
+        assert_eq!(transition.target, State::Ready);
+        assert_eq!(transition.end, Some(ClockTime::from_nseconds(300_000_000)));
+        assert_eq!(element.current, Some(State::Ready));
+        assert!(!element.stuck());
+    }
+}
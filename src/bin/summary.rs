@@ -0,0 +1,453 @@
+use std::collections::{BTreeMap, HashMap, VecDeque};
+use std::hash::Hash;
+use std::str::FromStr;
+
+use failure::Error;
+use gst_log_parser::{open_log, try_parse, ClockTime, DebugLevel, Entry, Filter};
+use itertools::Itertools;
+use structopt::StructOpt;
+
+#[derive(StructOpt)]
+#[structopt(
+    name = "summary",
+    about = "Give an overview of a log: duration, busiest categories, threads and objects, first errors"
+)]
+struct Opt {
+    #[structopt(help = "Input file, or '-' for stdin")]
+    input: String,
+    #[structopt(
+        long = "format",
+        default_value = "text",
+        help = "Output format: text or json"
+    )]
+    format: Format,
+    #[structopt(
+        short = "n",
+        long = "top",
+        default_value = "10",
+        help = "Number of items listed in each ranking"
+    )]
+    top: usize,
+    #[structopt(
+        short = "C",
+        long = "context",
+        default_value = "3",
+        help = "Number of entries displayed before and after the first error and warning"
+    )]
+    context: usize,
+    #[structopt(
+        short = "i",
+        long = "interval",
+        default_value = "1s",
+        parse(try_from_str = "parse_interval"),
+        help = "Period over which the entry rate is computed, such as '1s' or '500ms'"
+    )]
+    interval: u64,
+    #[structopt(
+        long = "filter",
+        help = "Only process entries matching this filter, such as 'level<=WARN && category~v4l2*' or a GST_DEBUG list"
+    )]
+    filter: Option<Filter>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum Format {
+    Text,
+    Json,
+}
+
+impl FromStr for Format {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Format, String> {
+        match s {
+            "text" => Ok(Format::Text),
+            "json" => Ok(Format::Json),
+            _ => Err(format!("unknown format '{}'", s)),
+        }
+    }
+}
+
+// Parse a non-zero duration, in nanoseconds
+fn parse_interval(s: &str) -> Result<u64, String> {
+    s.parse::<ClockTime>()
+        .ok()
+        .and_then(|t| t.nanoseconds())
+        .filter(|t| *t > 0)
+        .ok_or_else(|| format!("invalid interval '{}'", s))
+}
+
+// An entry with the ones logged around it
+struct Excerpt {
+    before: Vec<Entry>,
+    entry: Entry,
+    after: Vec<Entry>,
+}
+
+#[derive(Default)]
+struct Summary {
+    context: usize,
+    interval: u64,
+    entries: u64,
+    skipped_lines: usize,
+    first_ts: Option<u64>,
+    last_ts: Option<u64>,
+    levels: BTreeMap<DebugLevel, u64>,
+    categories: HashMap<String, u64>,
+    threads: HashMap<String, u64>,
+    pids: HashMap<u32, u64>,
+    // Indexed by `file:line:function`
+    locations: HashMap<String, u64>,
+    objects: HashMap<String, u64>,
+    first_error: Option<Excerpt>,
+    first_warning: Option<Excerpt>,
+    // Latest entries, the context of the next error or warning
+    previous: VecDeque<Entry>,
+    // Number of entries in each interval, indexed by the number of the interval
+    rate: BTreeMap<u64, u64>,
+}
+
+fn count<K: Eq + Hash>(counts: &mut HashMap<K, u64>, key: K) {
+    *counts.entry(key).or_insert(0) += 1;
+}
+
+// The @n keys with the highest count
+fn top<K: Ord + Clone>(counts: &HashMap<K, u64>, n: usize) -> Vec<(K, u64)> {
+    counts
+        .iter()
+        .sorted_by(|(ka, a), (kb, b)| b.cmp(a).then_with(|| ka.cmp(kb)))
+        .into_iter()
+        .take(n)
+        .map(|(k, count)| (k.clone(), *count))
+        .collect()
+}
+
+impl Summary {
+    fn new(context: usize, interval: u64) -> Summary {
+        Summary {
+            context,
+            interval,
+            ..Default::default()
+        }
+    }
+
+    fn add(&mut self, entry: Entry) {
+        self.entries += 1;
+
+        if let Some(ts) = entry.ts.nanoseconds() {
+            self.first_ts = Some(self.first_ts.unwrap_or(ts).min(ts));
+            self.last_ts = Some(self.last_ts.unwrap_or(ts).max(ts));
+            *self.rate.entry(ts / self.interval).or_insert(0) += 1;
+        }
+
+        *self.levels.entry(entry.level).or_insert(0) += 1;
+        count(&mut self.categories, entry.category.clone());
+        count(&mut self.threads, entry.thread.clone());
+        count(&mut self.pids, entry.pid);
+        count(
+            &mut self.locations,
+            format!("{}:{}:{}", entry.file, entry.line, entry.function),
+        );
+        if let Some(object) = &entry.object {
+            count(&mut self.objects, object.clone());
+        }
+
+        for excerpt in self
+            .first_error
+            .iter_mut()
+            .chain(self.first_warning.iter_mut())
+        {
+            if excerpt.after.len() < self.context {
+                excerpt.after.push(entry.clone());
+            }
+        }
+
+        let first = match entry.level {
+            DebugLevel::Error => Some(&mut self.first_error),
+            DebugLevel::Warning => Some(&mut self.first_warning),
+            _ => None,
+        };
+        if let Some(first) = first {
+            if first.is_none() {
+                *first = Some(Excerpt {
+                    before: self.previous.iter().cloned().collect(),
+                    entry: entry.clone(),
+                    after: Vec::new(),
+                });
+            }
+        }
+
+        if self.context > 0 {
+            if self.previous.len() == self.context {
+                self.previous.pop_front();
+            }
+            self.previous.push_back(entry);
+        }
+    }
+
+    fn duration(&self) -> ClockTime {
+        match (self.first_ts, self.last_ts) {
+            (Some(first), Some(last)) => ClockTime::from_nseconds(last - first),
+            _ => ClockTime::none(),
+        }
+    }
+
+    // Number of entries per second during each interval containing entries
+    fn rates(&self) -> Vec<(ClockTime, f64)> {
+        self.rate
+            .iter()
+            .map(|(i, count)| {
+                (
+                    ClockTime::from_nseconds(i * self.interval),
+                    *count as f64 * 1_000_000_000.0 / self.interval as f64,
+                )
+            })
+            .collect()
+    }
+
+    fn print_ranking<K: Ord + Clone + ToString>(
+        &self,
+        title: &str,
+        counts: &HashMap<K, u64>,
+        n: usize,
+    ) {
+        println!(
+            "\n{} (top {} of {}):",
+            title,
+            n.min(counts.len()),
+            counts.len()
+        );
+        for (key, count) in top(counts, n) {
+            println!("  {:<60} {:>10}", key.to_string(), count);
+        }
+    }
+
+    fn print_excerpt(title: &str, excerpt: &Option<Excerpt>) {
+        println!("\n{}:", title);
+        match excerpt {
+            Some(excerpt) => {
+                for entry in &excerpt.before {
+                    println!("  {}", entry);
+                }
+                println!("> {}", excerpt.entry);
+                for entry in &excerpt.after {
+                    println!("  {}", entry);
+                }
+            }
+            None => println!("  none"),
+        }
+    }
+
+    fn print_text(&self, n: usize) {
+        let time = |ts: Option<u64>| ClockTime::from(ts).to_string();
+        println!(
+            "Duration: {} (from {} to {})",
+            self.duration(),
+            time(self.first_ts),
+            time(self.last_ts)
+        );
+        println!("Entries: {}", self.entries);
+        if self.skipped_lines > 0 {
+            println!("Lines not parsed: {}", self.skipped_lines);
+        }
+
+        println!("\nLevels:");
+        for (level, count) in &self.levels {
+            println!("  {:<60} {:>10}", level.as_str(), count);
+        }
+
+        self.print_ranking("Categories", &self.categories, n);
+        self.print_ranking("Threads", &self.threads, n);
+        self.print_ranking("Processes", &self.pids, n);
+        self.print_ranking("Source locations", &self.locations, n);
+        self.print_ranking("Objects", &self.objects, n);
+
+        Summary::print_excerpt("First error", &self.first_error);
+        Summary::print_excerpt("First warning", &self.first_warning);
+
+        let rates = self.rates();
+        let max = rates.iter().map(|(_, rate)| *rate).fold(0.0, f64::max);
+        println!("\nEntries per second:");
+        let mut previous: Option<ClockTime> = None;
+        for (ts, rate) in rates {
+            // Intervals without any entry are skipped
+            if let Some(previous) = previous {
+                if ts - previous > ClockTime::from_nseconds(self.interval) {
+                    println!("  ...");
+                }
+            }
+            previous = Some(ts);
+
+            let bar = if max > 0.0 {
+                (rate / max * 50.0).round() as usize
+            } else {
+                0
+            };
+            println!("  {} {:>12.1} {}", ts, rate, "#".repeat(bar));
+        }
+    }
+
+    #[cfg(feature = "serialize")]
+    fn print_json(&self, n: usize) -> Result<(), Error> {
+        use serde_json::json;
+
+        fn ranking<K: Ord + Clone + ToString>(
+            counts: &HashMap<K, u64>,
+            n: usize,
+        ) -> serde_json::Value {
+            json!({
+                "total": counts.len(),
+                "top": top(counts, n)
+                    .into_iter()
+                    .map(|(key, count)| json!({ "name": key.to_string(), "count": count }))
+                    .collect::<Vec<_>>(),
+            })
+        }
+
+        fn excerpt(excerpt: &Option<Excerpt>) -> serde_json::Value {
+            match excerpt {
+                Some(excerpt) => json!({
+                    "before": excerpt.before,
+                    "entry": excerpt.entry,
+                    "after": excerpt.after,
+                }),
+                None => serde_json::Value::Null,
+            }
+        }
+
+        let levels: serde_json::Map<String, serde_json::Value> = self
+            .levels
+            .iter()
+            .map(|(level, count)| (level.as_str().to_string(), json!(count)))
+            .collect();
+        let rates: Vec<serde_json::Value> = self
+            .rates()
+            .into_iter()
+            .map(|(ts, rate)| json!({ "ts": ts.nanoseconds(), "entries_per_second": rate }))
+            .collect();
+
+        let summary = json!({
+            "duration": self.duration().nanoseconds(),
+            "first_ts": self.first_ts,
+            "last_ts": self.last_ts,
+            "entries": self.entries,
+            "skipped_lines": self.skipped_lines,
+            "levels": levels,
+            "categories": ranking(&self.categories, n),
+            "threads": ranking(&self.threads, n),
+            "pids": ranking(&self.pids, n),
+            "locations": ranking(&self.locations, n),
+            "objects": ranking(&self.objects, n),
+            "first_error": excerpt(&self.first_error),
+            "first_warning": excerpt(&self.first_warning),
+            "rate": rates,
+        });
+
+        println!("{}", serde_json::to_string_pretty(&summary)?);
+        Ok(())
+    }
+
+    #[cfg(not(feature = "serialize"))]
+    fn print_json(&self, _n: usize) -> Result<(), Error> {
+        Err(failure::format_err!(
+            "Rebuild with the 'serialize' feature to output JSON"
+        ))
+    }
+}
+
+fn main() -> Result<(), Error> {
+    let opt = Opt::from_args();
+    let input = open_log(&opt.input)?;
+    let filter = opt.filter.unwrap_or_default();
+
+    let mut summary = Summary::new(opt.context, opt.interval);
+    let mut parsed = try_parse(input);
+    for entry in parsed.by_ref().filter_map(Result::ok) {
+        if filter.matches(&entry) {
+            summary.add(entry);
+        }
+    }
+    summary.skipped_lines = parsed.stats().skipped_lines;
+
+    match opt.format {
+        Format::Text => summary.print_text(opt.top),
+        Format::Json => summary.print_json(opt.top)?,
+    }
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use gst_log_parser::parse;
+
+    fn summary(context: usize, interval: u64, log: &str) -> Summary {
+        let mut summary = Summary::new(context, interval);
+        for entry in parse(log.as_bytes()) {
+            summary.add(entry);
+        }
+        summary
+    }
+
+    fn messages(entries: &[Entry]) -> Vec<&str> {
+        entries.iter().map(|e| e.message.as_str()).collect()
+    }
+
+    const LOG: &str = "0:00:00.100000000 1 0x1 DEBUG test test.c:1:f: d1
+0:00:00.200000000 1 0x1 DEBUG test test.c:1:f: d2
+0:00:00.300000000 1 0x1 DEBUG test test.c:1:f: d3
+0:00:00.400000000 1 0x1 ERROR test test.c:2:f: e1
+0:00:00.600000000 1 0x1 DEBUG test test.c:1:f: d4
+0:00:00.700000000 1 0x1 WARN test test.c:3:f: w1
+0:00:00.800000000 1 0x1 DEBUG test test.c:1:f: d5
+0:00:00.900000000 1 0x1 DEBUG test test.c:1:f: d6
+0:00:01.000000000 1 0x1 DEBUG test test.c:1:f: d7
+0:00:02.100000000 1 0x1 ERROR test test.c:2:f: e2
+";
+
+    #[test]
+    fn excerpts() {
+        let summary = summary(2, 1_000_000_000, LOG);
+        assert_eq!(summary.entries, 10);
+
+        let error = summary.first_error.as_ref().unwrap();
+        assert_eq!(messages(&error.before), vec!["d2", "d3"]);
+        assert_eq!(error.entry.message, "e1");
+        assert_eq!(messages(&error.after), vec!["d4", "w1"]);
+
+        let warning = summary.first_warning.as_ref().unwrap();
+        assert_eq!(messages(&warning.before), vec!["e1", "d4"]);
+        assert_eq!(warning.entry.message, "w1");
+        assert_eq!(messages(&warning.after), vec!["d5", "d6"]);
+    }
+
+    #[test]
+    fn no_context() {
+        let summary = summary(0, 1_000_000_000, LOG);
+
+        let error = summary.first_error.as_ref().unwrap();
+        assert!(error.before.is_empty());
+        assert_eq!(error.entry.message, "e1");
+        assert!(error.after.is_empty());
+        assert!(summary.previous.is_empty());
+    }
+
+    #[test]
+    fn rate() {
+        let summary = summary(0, 500_000_000, LOG);
+        assert_eq!(summary.duration(), ClockTime::from_nseconds(2_000_000_000));
+
+        let buckets: Vec<(u64, u64)> = summary.rate.iter().map(|(i, c)| (*i, *c)).collect();
+        assert_eq!(buckets, vec![(0, 4), (1, 4), (2, 1), (4, 1)]);
+        assert_eq!(
+            summary.rates(),
+            vec![
+                (ClockTime::from_nseconds(0), 8.0),
+                (ClockTime::from_nseconds(500_000_000), 8.0),
+                (ClockTime::from_nseconds(1_000_000_000), 2.0),
+                (ClockTime::from_nseconds(2_000_000_000), 2.0),
+            ]
+        );
+    }
+}
@@ -1,11 +1,7 @@
-use std::fs::File;
 use std::process::exit;
 
 extern crate gst_log_parser;
-use gst_log_parser::parse;
-
-extern crate gstreamer as gst;
-use gst::ClockTime;
+use gst_log_parser::{open_log, parse, ClockTime, Filter};
 
 use std::collections::HashMap;
 extern crate itertools;
@@ -21,8 +17,15 @@ use structopt::StructOpt;
     about = "Track progress of frames across OMX components"
 )]
 struct Opt {
-    #[structopt(help = "Input file, generated with GST_DEBUG=\"OMX_API_TRACE:7\"")]
+    #[structopt(
+        help = "Input file, generated with GST_DEBUG=\"OMX_API_TRACE:7\", or '-' for stdin"
+    )]
     input: String,
+    #[structopt(
+        long = "filter",
+        help = "Only process entries matching this filter, such as 'level<=WARN && category~v4l2*' or a GST_DEBUG list"
+    )]
+    filter: Option<Filter>,
 }
 
 #[derive(Debug)]
@@ -155,20 +158,20 @@ impl CbTime {
 
 fn generate() -> Result<bool, std::io::Error> {
     let opt = Opt::from_args();
-    let input = File::open(opt.input)?;
-    let parsed = parse(input).filter(|entry| entry.category == "OMX_API_TRACE");
+    let input = open_log(opt.input)?;
+    let filter = opt.filter.unwrap_or_default();
+    let parsed = parse(input)
+        .filter(|entry| entry.category == "OMX_API_TRACE")
+        .filter(|entry| filter.matches(entry));
 
     let mut frames: HashMap<u64, Frame> = HashMap::new();
     // comp -> CbTime
     let mut cbs: HashMap<String, CbTime> = HashMap::new();
 
     for entry in parsed {
-        let s = entry.message_to_struct().expect("Failed to parse struct");
-        let object = entry.object.unwrap();
-        // Extract the component name by taking the 4th last chars of the gst object name
-        if let Some((i, _)) = object.char_indices().rev().nth(3) {
-            let comp_name = &object[i..];
-
+        let s = entry.message_structure().expect("Failed to parse struct");
+        // OMX calls are logged using the element wrapping the component
+        if let Some(comp_name) = entry.log_object().and_then(|object| object.omx_component()) {
             let omx_ts = s.get("TimeStamp");
             if omx_ts.is_none() {
                 continue;
@@ -0,0 +1,208 @@
+use std::collections::{BTreeSet, HashMap};
+use std::io;
+use std::io::Write;
+
+use failure::Error;
+use gst_log_parser::{open_log, try_parse, ClockTime, DebugLevel, Entry, Filter};
+use lazy_static::lazy_static;
+use regex::Regex;
+use structopt::StructOpt;
+
+#[derive(StructOpt)]
+#[structopt(
+    name = "triage",
+    about = "List the errors, warnings and fixmes of a log, grouping the repeated ones"
+)]
+struct Opt {
+    #[structopt(help = "Input file, or '-' for stdin")]
+    input: String,
+    #[structopt(
+        short = "t",
+        long = "time-order",
+        help = "Sort the groups by first occurrence rather than by number of entries"
+    )]
+    time_order: bool,
+    #[structopt(
+        short = "o",
+        long = "objects",
+        default_value = "5",
+        help = "Maximum number of objects listed for each group"
+    )]
+    objects: usize,
+    #[structopt(
+        long = "filter",
+        help = "Only process entries matching this filter, such as 'level<=WARN && category~v4l2*' or a GST_DEBUG list"
+    )]
+    filter: Option<Filter>,
+}
+
+lazy_static! {
+    static ref TIME: Regex = Regex::new(r"\b\d+:\d{2}:\d{2}\.\d+\b").unwrap();
+    // NULL pointers are printed as `(nil)` by glibc
+    static ref POINTER: Regex = Regex::new(r"\b0x[0-9a-fA-F]+\b|\(nil\)").unwrap();
+    static ref NUMBER: Regex = Regex::new(r"\b\d+(\.\d+)?\b").unwrap();
+}
+
+// Mask the parts of @message which usually differ between occurrences of the same problem
+fn template(message: &str) -> String {
+    let message = TIME.replace_all(message, "<time>");
+    let message = POINTER.replace_all(&message, "<ptr>");
+    NUMBER.replace_all(&message, "<n>").into_owned()
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+struct Key {
+    level: DebugLevel,
+    category: String,
+    // `file:line:function`
+    location: String,
+    template: String,
+}
+
+struct Group {
+    count: u64,
+    first: ClockTime,
+    last: ClockTime,
+    // Position of the first occurrence in the log
+    order: usize,
+    objects: BTreeSet<String>,
+}
+
+// Listed explicitly as `DebugLevel::None` also sorts before them
+const PROBLEM_LEVELS: [DebugLevel; 3] = [DebugLevel::Error, DebugLevel::Warning, DebugLevel::Fixme];
+
+fn location(entry: &Entry) -> String {
+    format!("{}:{}:{}", entry.file, entry.line, entry.function)
+}
+
+fn print_group(
+    out: &mut impl Write,
+    key: &Key,
+    group: &Group,
+    max_objects: usize,
+) -> io::Result<()> {
+    writeln!(
+        out,
+        "{} {}x {} {}",
+        key.level, group.count, key.category, key.location
+    )?;
+    writeln!(out, "    {}", key.template)?;
+    writeln!(out, "    first: {} last: {}", group.first, group.last)?;
+
+    if !group.objects.is_empty() {
+        let objects: Vec<&str> = group
+            .objects
+            .iter()
+            .take(max_objects)
+            .map(String::as_str)
+            .collect();
+        let more = group.objects.len() - objects.len();
+        if more > 0 {
+            writeln!(out, "    objects: {} (+{} more)", objects.join(", "), more)?;
+        } else {
+            writeln!(out, "    objects: {}", objects.join(", "))?;
+        }
+    }
+
+    writeln!(out)
+}
+
+fn main() -> Result<(), Error> {
+    let opt = Opt::from_args();
+    let input = open_log(&opt.input)?;
+    let filter = opt.filter.unwrap_or_default();
+
+    let mut groups: HashMap<Key, Group> = HashMap::new();
+    let parsed = try_parse(input)
+        .filter_map(Result::ok)
+        .filter(|entry| PROBLEM_LEVELS.contains(&entry.level) && filter.matches(entry));
+    for entry in parsed {
+        let key = Key {
+            level: entry.level,
+            category: entry.category.clone(),
+            location: location(&entry),
+            template: template(&entry.message),
+        };
+        let order = groups.len();
+        let group = groups.entry(key).or_insert_with(|| Group {
+            count: 0,
+            first: entry.ts,
+            last: entry.ts,
+            order,
+            objects: BTreeSet::new(),
+        });
+
+        group.count += 1;
+        group.first = group.first.min(entry.ts);
+        group.last = group.last.max(entry.ts);
+        if let Some(object) = entry.object {
+            group.objects.insert(object);
+        }
+    }
+
+    let mut groups: Vec<(Key, Group)> = groups.into_iter().collect();
+    if opt.time_order {
+        groups.sort_by_key(|(_, group)| group.order);
+    } else {
+        groups.sort_by(|(ka, a), (kb, b)| {
+            b.count
+                .cmp(&a.count)
+                .then_with(|| ka.level.cmp(&kb.level))
+                .then_with(|| a.order.cmp(&b.order))
+        });
+    }
+
+    let stdout = io::stdout();
+    let mut out = stdout.lock();
+    for (key, group) in &groups {
+        print_group(&mut out, key, group, opt.objects)?;
+    }
+    let total: u64 = groups.iter().map(|(_, group)| group.count).sum();
+    writeln!(out, "{} entries in {} groups", total, groups.len())?;
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn pointers() {
+        assert_eq!(
+            template("buffer 0x7f3a2c0041b0 dropped"),
+            "buffer <ptr> dropped"
+        );
+        assert_eq!(
+            template("buffer 0x7F3A2C0041B0 dropped"),
+            "buffer <ptr> dropped"
+        );
+        assert_eq!(template("buffer (nil) dropped"), "buffer <ptr> dropped");
+        assert_eq!(
+            template("pool 0x55d0 has buffer (nil)"),
+            template("pool 0x55e8 has buffer 0x7f3a")
+        );
+    }
+
+    #[test]
+    fn numbers() {
+        assert_eq!(template("frame 12 of 300"), "frame <n> of <n>");
+        assert_eq!(template("rate 29.97 fps"), "rate <n> fps");
+        assert_eq!(template("-1 is invalid"), "-<n> is invalid");
+        // Part of a name
+        assert_eq!(template("v4l2src0 failed"), "v4l2src0 failed");
+    }
+
+    #[test]
+    fn times() {
+        assert_eq!(template("late by 0:00:01.234"), "late by <time>");
+        assert_eq!(
+            template("timestamp 0:00:01.234000000 duration 99:99:99.999999999"),
+            "timestamp <time> duration <time>"
+        );
+        assert_eq!(
+            template("segment start 99:99:99.999999999"),
+            "segment start <time>"
+        );
+    }
+}
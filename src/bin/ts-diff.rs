@@ -1,12 +1,8 @@
 use std::collections::HashMap;
-use std::fs::File;
 use std::process::exit;
 
 extern crate gst_log_parser;
-use gst_log_parser::parse;
-
-extern crate gstreamer as gst;
-use gst::ClockTime;
+use gst_log_parser::{can_follow, open_log, try_parse, ClockTime, Filter};
 
 extern crate colored;
 use colored::*;
@@ -24,7 +20,7 @@ use structopt::StructOpt;
     about = "Display the timestamp difference between the previous entry from the thread"
 )]
 struct Opt {
-    #[structopt(help = "Input log file")]
+    #[structopt(help = "Input log file, or '-' for stdin")]
     input: String,
     #[structopt(
         short = "p",
@@ -34,6 +30,17 @@ struct Opt {
     top: usize,
     #[structopt(short = "s", help = "Sort by decreasing ts difference")]
     sort: bool,
+    #[structopt(
+        short = "f",
+        long = "follow",
+        help = "Wait for more data once the end of the input has been reached, entries are displayed as they are parsed"
+    )]
+    follow: bool,
+    #[structopt(
+        long = "filter",
+        help = "Only process entries matching this filter, such as 'level<=WARN && category~v4l2*' or a GST_DEBUG list"
+    )]
+    filter: Option<Filter>,
 }
 
 struct TsEntry {
@@ -58,27 +65,67 @@ impl TsEntry {
             top: true,
         }
     }
+
+    fn display(&self) {
+        let diff = {
+            if self.top {
+                self.diff.to_string().red().to_string()
+            } else {
+                self.diff.to_string()
+            }
+        };
+
+        println!(
+            "{} ({}) {} {:?} {} {}:{}:{}:<{}> {}",
+            self.entry.ts,
+            diff,
+            self.entry.thread,
+            self.entry.level,
+            self.entry.category,
+            self.entry.file,
+            self.entry.line,
+            self.entry.function,
+            self.entry.object.clone().unwrap_or_else(|| "".to_string()),
+            self.entry.message
+        );
+    }
 }
 
 fn generate() -> Result<bool, std::io::Error> {
     let opt = Opt::from_args();
-    let input = File::open(opt.input)?;
-
-    let parsed = parse(input);
-    let mut previous: HashMap<String, ClockTime> = HashMap::new();
+    // Pipes are over once their end has been reached
+    let follow = opt.follow && can_follow(&opt.input);
+    let input = open_log(opt.input)?;
+
+    let filter = opt.filter.unwrap_or_default();
+    let parsed = try_parse(input)
+        .follow(follow)
+        .filter_map(Result::ok)
+        .filter(|entry| filter.matches(entry));
+    // Threads of different processes, from merged logs, may have the same address
+    let mut previous: HashMap<(u32, String), ClockTime> = HashMap::new();
 
     // Compute ts diff
     let entries = parsed.map(|entry| {
-        let diff = match previous.get(&entry.thread) {
+        let thread = (entry.pid, entry.thread.clone());
+        let diff = match previous.get(&thread) {
             Some(p) => entry.ts - *p,
             None => ClockTime::from_seconds(0),
         };
 
-        previous.insert(entry.thread.clone(), entry.ts);
+        previous.insert(thread, entry.ts);
 
         TsEntry::new(entry, diff)
     });
 
+    if opt.follow {
+        // Can't sort nor highlight entries as the input never ends
+        for e in entries {
+            e.display();
+        }
+        return Ok(true);
+    }
+
     // Sort by ts diff
     let entries = entries.sorted_by(|a, b| Ord::cmp(&b.diff, &a.diff));
 
@@ -107,27 +154,7 @@ fn generate() -> Result<bool, std::io::Error> {
 
     // Display
     for e in entries {
-        let diff = {
-            if e.top {
-                e.diff.to_string().red().to_string()
-            } else {
-                e.diff.to_string()
-            }
-        };
-
-        println!(
-            "{} ({}) {} {:?} {} {}:{}:{}:<{}> {}",
-            e.entry.ts,
-            diff,
-            e.entry.thread,
-            e.entry.level,
-            e.entry.category,
-            e.entry.file,
-            e.entry.line,
-            e.entry.function,
-            e.entry.object.clone().unwrap_or_else(|| "".to_string()),
-            e.entry.message
-        );
+        e.display();
     }
 
     Ok(true)
@@ -0,0 +1,236 @@
+use std::fmt;
+use std::ops;
+use std::str::FromStr;
+
+use crate::datetime;
+
+/// A time in nanoseconds, or `None` if unknown. Same API as `gstreamer::ClockTime`
+/// without requiring the GStreamer runtime.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash, Default)]
+pub struct ClockTime(pub Option<u64>);
+
+impl ClockTime {
+    pub fn from_seconds(seconds: u64) -> ClockTime {
+        ClockTime(seconds.checked_mul(1_000_000_000))
+    }
+
+    pub fn from_mseconds(mseconds: u64) -> ClockTime {
+        ClockTime(mseconds.checked_mul(1_000_000))
+    }
+
+    pub fn from_useconds(useconds: u64) -> ClockTime {
+        ClockTime(useconds.checked_mul(1_000))
+    }
+
+    pub fn from_nseconds(nseconds: u64) -> ClockTime {
+        ClockTime(Some(nseconds))
+    }
+
+    pub fn none() -> ClockTime {
+        ClockTime(None)
+    }
+
+    pub fn is_some(&self) -> bool {
+        self.0.is_some()
+    }
+
+    pub fn is_none(&self) -> bool {
+        self.0.is_none()
+    }
+
+    pub fn seconds(&self) -> Option<u64> {
+        self.0.map(|t| t / 1_000_000_000)
+    }
+
+    pub fn mseconds(&self) -> Option<u64> {
+        self.0.map(|t| t / 1_000_000)
+    }
+
+    pub fn useconds(&self) -> Option<u64> {
+        self.0.map(|t| t / 1_000)
+    }
+
+    pub fn nseconds(&self) -> Option<u64> {
+        self.0
+    }
+
+    pub fn nanoseconds(&self) -> Option<u64> {
+        self.0
+    }
+}
+
+impl From<u64> for ClockTime {
+    fn from(t: u64) -> ClockTime {
+        ClockTime(Some(t))
+    }
+}
+
+impl From<Option<u64>> for ClockTime {
+    fn from(t: Option<u64>) -> ClockTime {
+        ClockTime(t)
+    }
+}
+
+/// Parse a time using a unit, such as `1.5s`, `200ms`, `10us`, `5ns`, `2m` or `1h`,
+/// or GStreamer's `0:00:01.5` format
+impl FromStr for ClockTime {
+    type Err = ();
+
+    fn from_str(s: &str) -> Result<ClockTime, ()> {
+        parse(s).map(ClockTime::from_nseconds).ok_or(())
+    }
+}
+
+// Parse a time in nanoseconds
+fn parse(s: &str) -> Option<u64> {
+    fn digits(s: &str) -> Option<&str> {
+        if !s.is_empty() && s.bytes().all(|c| c.is_ascii_digit()) {
+            Some(s)
+        } else {
+            None
+        }
+    }
+
+    fn split_fraction(s: &str) -> Option<(u64, u64)> {
+        let mut split = s.splitn(2, '.');
+        let int = digits(split.next()?)?.parse().ok()?;
+        let fraction = match split.next() {
+            Some(fraction) => u64::from(datetime::parse_nanoseconds(digits(fraction)?)),
+            None => 0,
+        };
+        Some((int, fraction))
+    }
+
+    if s.contains(':') {
+        let mut split = s.splitn(3, ':');
+        let h: u64 = digits(split.next()?)?.parse().ok()?;
+        let m: u64 = digits(split.next()?)?.parse().ok()?;
+        let (secs, ns) = split_fraction(split.next()?)?;
+        return h
+            .checked_mul(3600)?
+            .checked_add(m.checked_mul(60)?)?
+            .checked_add(secs)?
+            .checked_mul(1_000_000_000)?
+            .checked_add(ns);
+    }
+
+    let unit_start = s.find(|c: char| c.is_ascii_alphabetic())?;
+    let unit: u64 = match &s[unit_start..] {
+        "ns" => 1,
+        "us" => 1_000,
+        "ms" => 1_000_000,
+        "s" => 1_000_000_000,
+        "m" | "min" => 60 * 1_000_000_000,
+        "h" => 60 * 60 * 1_000_000_000,
+        _ => return None,
+    };
+    let (int, fraction) = split_fraction(&s[..unit_start])?;
+
+    // The fraction is less than a second so this can't overflow once divided
+    let fraction = u128::from(fraction) * u128::from(unit) / 1_000_000_000;
+
+    int.checked_mul(unit)?.checked_add(fraction as u64)
+}
+
+impl fmt::Display for ClockTime {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self.0 {
+            None => f.write_str("99:99:99.999999999"),
+            Some(t) => write!(
+                f,
+                "{:02}:{:02}:{:02}.{:09}",
+                t / 3_600_000_000_000,
+                (t / 60_000_000_000) % 60,
+                (t / 1_000_000_000) % 60,
+                t % 1_000_000_000
+            ),
+        }
+    }
+}
+
+// Operations on unknown times, or overflowing, result in unknown times
+macro_rules! impl_op {
+    ($op:ident, $op_name:ident, $assign:ident, $assign_name:ident, $checked:ident) => {
+        impl ops::$op for ClockTime {
+            type Output = ClockTime;
+
+            fn $op_name(self, other: ClockTime) -> ClockTime {
+                match (self.0, other.0) {
+                    (Some(a), Some(b)) => ClockTime(a.$checked(b)),
+                    _ => ClockTime(None),
+                }
+            }
+        }
+
+        impl ops::$assign for ClockTime {
+            fn $assign_name(&mut self, other: ClockTime) {
+                *self = ops::$op::$op_name(*self, other);
+            }
+        }
+    };
+}
+
+impl_op!(Add, add, AddAssign, add_assign, checked_add);
+impl_op!(Sub, sub, SubAssign, sub_assign, checked_sub);
+
+#[cfg(feature = "gstreamer")]
+impl From<ClockTime> for gst::ClockTime {
+    fn from(t: ClockTime) -> gst::ClockTime {
+        gst::ClockTime(t.0)
+    }
+}
+
+#[cfg(feature = "gstreamer")]
+impl From<gst::ClockTime> for ClockTime {
+    fn from(t: gst::ClockTime) -> ClockTime {
+        ClockTime(t.0)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn display() {
+        assert_eq!(
+            ClockTime::from_nseconds(3_723_007_773_544).to_string(),
+            "01:02:03.007773544"
+        );
+        assert_eq!(ClockTime::none().to_string(), "99:99:99.999999999");
+    }
+
+    #[test]
+    fn ops() {
+        let t = ClockTime::from_seconds(2) + ClockTime::from_mseconds(5);
+        assert_eq!(t.nseconds(), Some(2_005_000_000));
+        assert_eq!(t.mseconds(), Some(2005));
+        assert_eq!((t - ClockTime::from_seconds(1)).useconds(), Some(1_005_000));
+        assert!((ClockTime::from_seconds(1) - t).is_none());
+        assert!((t + ClockTime::none()).is_none());
+
+        let mut t = ClockTime::from_nseconds(0);
+        t += ClockTime::from_useconds(3);
+        assert_eq!(t, ClockTime::from_nseconds(3000));
+        assert!(ClockTime::none() < t);
+    }
+
+    #[test]
+    fn from_str() {
+        let parse = |s: &str| s.parse::<ClockTime>().ok().and_then(|t| t.nanoseconds());
+
+        assert_eq!(parse("1s"), Some(1_000_000_000));
+        assert_eq!(parse("1.5s"), Some(1_500_000_000));
+        assert_eq!(parse("250ms"), Some(250_000_000));
+        assert_eq!(parse("0.5us"), Some(500));
+        assert_eq!(parse("2m"), Some(120_000_000_000));
+        assert_eq!(parse("0:01:02.000000003"), Some(62_000_000_003));
+        assert_eq!(parse("1.5m"), Some(90_000_000_000));
+        assert_eq!(parse("0.5h"), Some(1_800_000_000_000));
+        assert_eq!(parse("18446744073709551615:00:00.0"), None);
+        assert_eq!(parse("5124096:00:00.0"), None);
+        assert_eq!(parse("18446744073709551615h"), None);
+        assert_eq!(parse("5"), None);
+        assert_eq!(parse("5days"), None);
+    }
+}
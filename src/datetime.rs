@@ -0,0 +1,199 @@
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+
+use regex::Regex;
+
+// Absolute date and time, such as 2019-06-04T10:11:12.123456789+02:00, optionally within brackets
+pub(crate) const DATETIME: &str =
+    r"\[?\d{4}-\d{2}-\d{2}[T ]\d{2}:\d{2}:\d{2}(?:[.,]\d{1,9})?(?:Z|[+-]\d{2}:?\d{2})?\]?";
+
+lazy_static! {
+    static ref DATETIME_PREFIX: Regex = Regex::new(
+        r"^\[?(\d{4})-(\d{2})-(\d{2})[T ](\d{2}):(\d{2}):(\d{2})(?:[.,](\d{1,9}))?(Z|[+-]\d{2}:?\d{2})?\]?(?: +|$)"
+    )
+    .unwrap();
+}
+
+// Number of days between 1970-01-01 and the given date of the proleptic Gregorian calendar
+fn days_from_civil(year: i64, month: u32, day: u32) -> i64 {
+    let year = if month <= 2 { year - 1 } else { year };
+    let era = if year >= 0 { year } else { year - 399 } / 400;
+    let year_of_era = year - era * 400;
+    let month = i64::from((month + 9) % 12);
+    let day_of_year = (153 * month + 2) / 5 + i64::from(day) - 1;
+    let day_of_era = year_of_era * 365 + year_of_era / 4 - year_of_era / 100 + day_of_year;
+
+    era * 146_097 + day_of_era - 719_468
+}
+
+// Inverse of days_from_civil()
+fn civil_from_days(days: i64) -> (i64, u32, u32) {
+    let days = days + 719_468;
+    let era = if days >= 0 { days } else { days - 146_096 } / 146_097;
+    let day_of_era = days - era * 146_097;
+    let year_of_era =
+        (day_of_era - day_of_era / 1460 + day_of_era / 36_524 - day_of_era / 146_096) / 365;
+    let day_of_year = day_of_era - (365 * year_of_era + year_of_era / 4 - year_of_era / 100);
+    let month = (5 * day_of_year + 2) / 153;
+    let day = day_of_year - (153 * month + 2) / 5 + 1;
+    let month = if month < 10 { month + 3 } else { month - 9 };
+    let year = year_of_era + era * 400 + if month <= 2 { 1 } else { 0 };
+
+    (year, month as u32, day as u32)
+}
+
+/// Format @time as an UTC ISO 8601 date and time with nanoseconds precision
+pub(crate) fn format_datetime(time: SystemTime) -> String {
+    let since_epoch = time.duration_since(UNIX_EPOCH).unwrap_or_default();
+    let seconds = since_epoch.as_secs();
+    let (year, month, day) = civil_from_days((seconds / 86_400) as i64);
+    let seconds = seconds % 86_400;
+
+    format!(
+        "{:04}-{:02}-{:02}T{:02}:{:02}:{:02}.{:09}Z",
+        year,
+        month,
+        day,
+        seconds / 3600,
+        (seconds / 60) % 60,
+        seconds % 60,
+        since_epoch.subsec_nanos()
+    )
+}
+
+/// Convert an UTC date and time to a `SystemTime`, returning `None` if it is not valid
+/// or before the Unix epoch.
+pub(crate) fn system_time(
+    year: i64,
+    month: u32,
+    day: u32,
+    hour: u32,
+    minute: u32,
+    second: u32,
+    nanoseconds: u32,
+) -> Option<SystemTime> {
+    if !(1..=12).contains(&month)
+        || !(1..=31).contains(&day)
+        || hour > 23
+        || minute > 59
+        || second > 60
+    {
+        return None;
+    }
+
+    let days = days_from_civil(year, month, day);
+    let seconds = days * 86_400 + i64::from(hour * 3600 + minute * 60 + second);
+    if seconds < 0 {
+        return None;
+    }
+
+    Some(UNIX_EPOCH + Duration::new(seconds as u64, nanoseconds))
+}
+
+// Parse the fractional part of a second, such as "123" for 123 ms
+pub(crate) fn parse_nanoseconds(fraction: &str) -> u32 {
+    let digits = fraction.len().min(9);
+    let value: u32 = fraction[..digits].parse().unwrap_or(0);
+
+    value * 10u32.pow(9 - digits as u32)
+}
+
+// Parse an UTC offset such as "Z", "+02:00" or "-0500", in seconds
+fn parse_offset(offset: &str) -> i64 {
+    if offset == "Z" {
+        return 0;
+    }
+
+    let sign = if offset.starts_with('-') { -1 } else { 1 };
+    let digits: String = offset[1..].chars().filter(|c| *c != ':').collect();
+    let hours: i64 = digits[..2].parse().unwrap_or(0);
+    let minutes: i64 = digits[2..].parse().unwrap_or(0);
+
+    sign * (hours * 3600 + minutes * 60)
+}
+
+/// Split the absolute date and time prefixing @line, if any. Times without UTC offset are
+/// considered as being UTC.
+pub(crate) fn split_datetime(line: &str) -> (Option<SystemTime>, &str) {
+    let caps = match DATETIME_PREFIX.captures(line) {
+        None => return (None, line),
+        Some(caps) => caps,
+    };
+
+    let number = |i: usize| caps[i].parse::<u32>().unwrap_or(0);
+    let nanoseconds = caps.get(7).map_or(0, |m| parse_nanoseconds(m.as_str()));
+    let time = system_time(
+        i64::from(number(1)),
+        number(2),
+        number(3),
+        number(4),
+        number(5),
+        number(6),
+        nanoseconds,
+    );
+    let offset = caps.get(8).map_or(0, |m| parse_offset(m.as_str()));
+
+    let time = time.and_then(|time| {
+        if offset >= 0 {
+            time.checked_sub(Duration::from_secs(offset as u64))
+        } else {
+            time.checked_add(Duration::from_secs(-offset as u64))
+        }
+    });
+
+    match time {
+        // Leave invalid dates to the caller, it will fail to parse them
+        None => (None, line),
+        Some(time) => (Some(time), &line[caps.get(0).unwrap().end()..]),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn seconds(time: SystemTime) -> f64 {
+        let d = time.duration_since(UNIX_EPOCH).unwrap();
+        d.as_secs() as f64 + f64::from(d.subsec_nanos()) / 1e9
+    }
+
+    #[test]
+    fn datetime() {
+        let (time, rest) = split_datetime("2019-06-04T10:11:12.5Z 0:00:00.007773544");
+        assert_eq!(seconds(time.unwrap()), 1_559_643_072.5);
+        assert_eq!(rest, "0:00:00.007773544");
+
+        let (time, rest) = split_datetime("[2019-06-04 12:11:12,123456+02:00]   8874");
+        assert_eq!(seconds(time.unwrap()), 1_559_643_072.123456);
+        assert_eq!(rest, "8874");
+
+        let (time, _) = split_datetime("1970-01-01T00:00:00-0130 ");
+        assert_eq!(seconds(time.unwrap()), 5400.0);
+
+        let (time, rest) = split_datetime("0:00:00.007773544  8874");
+        assert!(time.is_none());
+        assert_eq!(rest, "0:00:00.007773544  8874");
+
+        let (time, rest) = split_datetime("2019-13-04T10:11:12Z 0:00:00.007773544");
+        assert!(time.is_none());
+        assert_eq!(rest, "2019-13-04T10:11:12Z 0:00:00.007773544");
+    }
+
+    #[test]
+    fn civil() {
+        assert_eq!(days_from_civil(1970, 1, 1), 0);
+        assert_eq!(days_from_civil(2000, 3, 1), 11_017);
+        assert_eq!(days_from_civil(1969, 12, 31), -1);
+
+        for days in &[-1, 0, 11_017, 18_051, 2_932_896] {
+            let (year, month, day) = civil_from_days(*days);
+            assert_eq!(days_from_civil(year, month, day), *days);
+        }
+    }
+
+    #[test]
+    fn format() {
+        let time = UNIX_EPOCH + Duration::new(1_559_643_072, 1000);
+        assert_eq!(format_datetime(time), "2019-06-04T10:11:12.000001000Z");
+        assert_eq!(split_datetime(&format_datetime(time)).0, Some(time));
+    }
+}
@@ -0,0 +1,94 @@
+use std::fmt;
+use std::str::FromStr;
+
+/// Level of a debug entry, ordered from the most to the least important.
+/// Same variants as `gstreamer::DebugLevel` without requiring the GStreamer runtime.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub enum DebugLevel {
+    None,
+    Error,
+    Warning,
+    Fixme,
+    Info,
+    Debug,
+    Log,
+    Trace,
+    Memdump,
+}
+
+impl DebugLevel {
+    /// The name of the level as written in logs, such as `WARN`
+    pub fn as_str(self) -> &'static str {
+        match self {
+            DebugLevel::None => "NONE",
+            DebugLevel::Error => "ERROR",
+            DebugLevel::Warning => "WARN",
+            DebugLevel::Fixme => "FIXME",
+            DebugLevel::Info => "INFO",
+            DebugLevel::Debug => "DEBUG",
+            DebugLevel::Log => "LOG",
+            DebugLevel::Trace => "TRACE",
+            DebugLevel::Memdump => "MEMDUMP",
+        }
+    }
+}
+
+/// Parse a level as accepted by `GST_DEBUG`: either its name, ignoring case, or its number
+impl FromStr for DebugLevel {
+    type Err = ();
+
+    fn from_str(s: &str) -> Result<DebugLevel, ()> {
+        match s.to_ascii_uppercase().as_str() {
+            "0" | "NONE" => Ok(DebugLevel::None),
+            "1" | "ERROR" => Ok(DebugLevel::Error),
+            "2" | "WARN" | "WARNING" => Ok(DebugLevel::Warning),
+            "3" | "FIXME" => Ok(DebugLevel::Fixme),
+            "4" | "INFO" => Ok(DebugLevel::Info),
+            "5" | "DEBUG" => Ok(DebugLevel::Debug),
+            "6" | "LOG" => Ok(DebugLevel::Log),
+            "7" | "TRACE" => Ok(DebugLevel::Trace),
+            "9" | "MEMDUMP" => Ok(DebugLevel::Memdump),
+            _ => Err(()),
+        }
+    }
+}
+
+impl fmt::Display for DebugLevel {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.write_str(self.as_str())
+    }
+}
+
+#[cfg(feature = "gstreamer")]
+impl From<DebugLevel> for gst::DebugLevel {
+    fn from(level: DebugLevel) -> gst::DebugLevel {
+        match level {
+            DebugLevel::None => gst::DebugLevel::None,
+            DebugLevel::Error => gst::DebugLevel::Error,
+            DebugLevel::Warning => gst::DebugLevel::Warning,
+            DebugLevel::Fixme => gst::DebugLevel::Fixme,
+            DebugLevel::Info => gst::DebugLevel::Info,
+            DebugLevel::Debug => gst::DebugLevel::Debug,
+            DebugLevel::Log => gst::DebugLevel::Log,
+            DebugLevel::Trace => gst::DebugLevel::Trace,
+            DebugLevel::Memdump => gst::DebugLevel::Memdump,
+        }
+    }
+}
+
+#[cfg(feature = "gstreamer")]
+impl From<gst::DebugLevel> for DebugLevel {
+    fn from(level: gst::DebugLevel) -> DebugLevel {
+        match level {
+            gst::DebugLevel::Error => DebugLevel::Error,
+            gst::DebugLevel::Warning => DebugLevel::Warning,
+            gst::DebugLevel::Fixme => DebugLevel::Fixme,
+            gst::DebugLevel::Info => DebugLevel::Info,
+            gst::DebugLevel::Debug => DebugLevel::Debug,
+            gst::DebugLevel::Log => DebugLevel::Log,
+            gst::DebugLevel::Trace => DebugLevel::Trace,
+            gst::DebugLevel::Memdump => DebugLevel::Memdump,
+            _ => DebugLevel::None,
+        }
+    }
+}
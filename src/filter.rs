@@ -0,0 +1,670 @@
+use std::error;
+use std::fmt;
+use std::str::FromStr;
+
+use crate::{ClockTime, DebugLevel, Entry};
+
+/// A predicate over entries, parsed from an expression such as
+/// `level<=WARN && category~"v4l2*" && object=="queue0" && ts in 1s..5s`.
+///
+/// Conditions compare a field to a value and can be combined using `&&`, `||`, `!` and
+/// parentheses. The supported fields are:
+/// - `level`, compared using the order of the levels, `ERROR` being the lowest
+/// - `ts`, the running time, using a unit such as `1.5s`, `200ms`, `10us`, `5ns`, `2m` or `1h`,
+///   or GStreamer's `0:00:01.5` format
+/// - `pid` and `line`
+/// - `category`, `object`, `element`, `pad`, `thread`, `file`, `function` and `message`
+///
+/// `==`, `!=`, `<`, `<=`, `>` and `>=` are supported for levels and numbers, text can be compared
+/// using `==` and `!=` or matched against a glob pattern using `~` and `!~`.
+/// `field in min..max` checks if a number is in a range, either end can be omitted.
+///
+/// A condition can also be a list of `category:level` thresholds, as used in `GST_DEBUG`.
+#[derive(Debug, Clone, PartialEq, Default)]
+pub struct Filter {
+    expr: Option<Expr>,
+}
+
+/// Error returned when failing to parse a filter
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ParseFilterError {
+    /// Byte offset of the error in the parsed text
+    pub position: usize,
+    pub reason: &'static str,
+}
+
+impl fmt::Display for ParseFilterError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{} at offset {}", self.reason, self.position)
+    }
+}
+
+impl error::Error for ParseFilterError {}
+
+type ParseResult<T> = Result<T, ParseFilterError>;
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum Cmp {
+    Eq,
+    Ne,
+    Lt,
+    Le,
+    Gt,
+    Ge,
+}
+
+impl Cmp {
+    fn compare<T: Ord>(self, a: T, b: T) -> bool {
+        match self {
+            Cmp::Eq => a == b,
+            Cmp::Ne => a != b,
+            Cmp::Lt => a < b,
+            Cmp::Le => a <= b,
+            Cmp::Gt => a > b,
+            Cmp::Ge => a >= b,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum Op {
+    Cmp(Cmp),
+    Glob,
+    NotGlob,
+}
+
+// Longest operators first so "<=" isn't parsed as "<"
+const OPERATORS: &[(&str, Op)] = &[
+    ("==", Op::Cmp(Cmp::Eq)),
+    ("!=", Op::Cmp(Cmp::Ne)),
+    ("!~", Op::NotGlob),
+    ("<=", Op::Cmp(Cmp::Le)),
+    (">=", Op::Cmp(Cmp::Ge)),
+    ("<", Op::Cmp(Cmp::Lt)),
+    (">", Op::Cmp(Cmp::Gt)),
+    ("~", Op::Glob),
+];
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum NumberField {
+    Ts,
+    Pid,
+    Line,
+}
+
+impl NumberField {
+    fn get(self, entry: &Entry) -> Option<u64> {
+        match self {
+            NumberField::Ts => entry.ts.nanoseconds(),
+            NumberField::Pid => Some(u64::from(entry.pid)),
+            NumberField::Line => Some(u64::from(entry.line)),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum TextField {
+    Category,
+    Object,
+    Element,
+    Pad,
+    Thread,
+    File,
+    Function,
+    Message,
+}
+
+impl TextField {
+    fn get(self, entry: &Entry) -> Option<&str> {
+        match self {
+            TextField::Category => Some(&entry.category),
+            TextField::Object => entry.object.as_deref(),
+            TextField::Element => entry.log_object().and_then(|o| o.element()),
+            TextField::Pad => entry.log_object().and_then(|o| o.pad()),
+            TextField::Thread => Some(&entry.thread),
+            TextField::File => Some(&entry.file),
+            TextField::Function => Some(&entry.function),
+            TextField::Message => Some(&entry.message),
+        }
+    }
+}
+
+/// `GST_DEBUG` thresholds: the level of the last pattern matching the category applies,
+/// or the default one if none does.
+#[derive(Debug, Clone, PartialEq)]
+struct DebugSpec {
+    default: DebugLevel,
+    thresholds: Vec<(String, DebugLevel)>,
+}
+
+impl DebugSpec {
+    fn matches(&self, entry: &Entry) -> bool {
+        let threshold = self
+            .thresholds
+            .iter()
+            .rev()
+            .find(|(pattern, _)| glob_match(pattern, &entry.category))
+            .map(|(_, level)| *level)
+            .unwrap_or(self.default);
+
+        entry.level != DebugLevel::None && entry.level <= threshold
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+enum Expr {
+    And(Box<Expr>, Box<Expr>),
+    Or(Box<Expr>, Box<Expr>),
+    Not(Box<Expr>),
+    Level(Cmp, DebugLevel),
+    Number(NumberField, Cmp, u64),
+    Range(NumberField, Option<u64>, Option<u64>),
+    Text(TextField, Cmp, String),
+    Glob(TextField, String),
+    Debug(DebugSpec),
+}
+
+impl Expr {
+    fn matches(&self, entry: &Entry) -> bool {
+        match self {
+            Expr::And(a, b) => a.matches(entry) && b.matches(entry),
+            Expr::Or(a, b) => a.matches(entry) || b.matches(entry),
+            Expr::Not(e) => !e.matches(entry),
+            Expr::Level(cmp, level) => cmp.compare(entry.level, *level),
+            Expr::Number(field, cmp, value) => match field.get(entry) {
+                Some(v) => cmp.compare(v, *value),
+                None => false,
+            },
+            Expr::Range(field, min, max) => match field.get(entry) {
+                Some(v) => min.unwrap_or(0) <= v && max.map(|max| v < max).unwrap_or(true),
+                None => false,
+            },
+            // Missing values, such as the object of an entry not having one, are different
+            // from any text
+            Expr::Text(field, cmp, value) => match field.get(entry) {
+                Some(v) => cmp.compare(v, value.as_str()),
+                None => *cmp == Cmp::Ne,
+            },
+            Expr::Glob(field, pattern) => match field.get(entry) {
+                Some(v) => glob_match(pattern, v),
+                None => false,
+            },
+            Expr::Debug(spec) => spec.matches(entry),
+        }
+    }
+}
+
+/// Match @s against a pattern where `*` matches any sequence of characters and `?` any
+/// single character
+fn glob_match(pattern: &str, s: &str) -> bool {
+    let pattern: Vec<char> = pattern.chars().collect();
+    let s: Vec<char> = s.chars().collect();
+    let (mut p, mut i) = (0, 0);
+    // Position of the last '*' in the pattern and of the text it has been matched with
+    let mut star = None;
+
+    while i < s.len() {
+        if p < pattern.len() && (pattern[p] == '?' || pattern[p] == s[i]) {
+            p += 1;
+            i += 1;
+        } else if p < pattern.len() && pattern[p] == '*' {
+            star = Some((p, i));
+            p += 1;
+        } else if let Some((star_p, star_i)) = star {
+            // Let the last '*' match one more character
+            p = star_p + 1;
+            i = star_i + 1;
+            star = Some((star_p, star_i + 1));
+        } else {
+            return false;
+        }
+    }
+
+    pattern[p..].iter().all(|c| *c == '*')
+}
+
+// Characters which can be used in unquoted words
+fn is_word_char(c: char) -> bool {
+    !c.is_whitespace() && !"<>=!~&|()\"".contains(c)
+}
+
+// Parse a level used as GST_DEBUG threshold, where numbers can be used for any level
+fn parse_threshold(s: &str) -> Option<DebugLevel> {
+    match s.parse::<u32>() {
+        Ok(8) => Some(DebugLevel::Trace),
+        Ok(n) if n > 9 => Some(DebugLevel::Memdump),
+        _ => s.parse().ok(),
+    }
+}
+
+// Parse a GST_DEBUG string such as "3,GST_TRACER:7,v4l2*:5"
+fn parse_debug_spec(s: &str) -> Option<DebugSpec> {
+    let mut spec = DebugSpec {
+        default: DebugLevel::Error,
+        thresholds: Vec::new(),
+    };
+
+    for item in s.split(',').map(str::trim).filter(|item| !item.is_empty()) {
+        match item.rfind(':') {
+            Some(pos) => {
+                let level = parse_threshold(&item[pos + 1..])?;
+                spec.thresholds.push((item[..pos].to_string(), level));
+            }
+            None => spec.default = parse_threshold(item)?,
+        }
+    }
+
+    Some(spec)
+}
+
+struct Parser<'a> {
+    s: &'a str,
+    pos: usize,
+}
+
+impl<'a> Parser<'a> {
+    fn error<T>(&self, reason: &'static str) -> ParseResult<T> {
+        self.error_at(self.pos, reason)
+    }
+
+    fn error_at<T>(&self, position: usize, reason: &'static str) -> ParseResult<T> {
+        Err(ParseFilterError { position, reason })
+    }
+
+    fn rest(&self) -> &'a str {
+        &self.s[self.pos..]
+    }
+
+    fn skip_whitespaces(&mut self) {
+        let rest = self.rest();
+        self.pos += rest.len() - rest.trim_start().len();
+    }
+
+    // Consume @token, ignoring the whitespaces preceding it
+    fn eat(&mut self, token: &str) -> bool {
+        self.skip_whitespaces();
+        if self.rest().starts_with(token) {
+            self.pos += token.len();
+            true
+        } else {
+            false
+        }
+    }
+
+    fn at_end(&mut self) -> bool {
+        self.skip_whitespaces();
+        self.rest().is_empty()
+    }
+
+    fn at_word(&mut self) -> bool {
+        self.skip_whitespaces();
+        match self.rest().chars().next() {
+            Some(c) => c == '"' || (is_word_char(c) && !self.rest().starts_with("..")),
+            None => false,
+        }
+    }
+
+    // Parse an unquoted word, stopping before ".." which separates the ends of ranges,
+    // or a quoted string
+    fn word(&mut self) -> ParseResult<String> {
+        self.skip_whitespaces();
+        if self.eat("\"") {
+            return self.quoted();
+        }
+
+        let start = self.pos;
+        let mut end = start;
+        for (i, c) in self.rest().char_indices() {
+            if !is_word_char(c) || self.s[start + i..].starts_with("..") {
+                break;
+            }
+            end = start + i + c.len_utf8();
+        }
+
+        if end == start {
+            return self.error("expected a value");
+        }
+        self.pos = end;
+        Ok(self.s[start..end].to_string())
+    }
+
+    fn quoted(&mut self) -> ParseResult<String> {
+        let mut result = String::new();
+        let mut chars = self.rest().char_indices();
+
+        while let Some((i, c)) = chars.next() {
+            match c {
+                '"' => {
+                    self.pos += i + 1;
+                    return Ok(result);
+                }
+                '\\' => match chars.next() {
+                    Some((_, c)) => result.push(c),
+                    None => break,
+                },
+                c => result.push(c),
+            }
+        }
+
+        self.error_at(self.s.len(), "unterminated string")
+    }
+
+    fn or(&mut self) -> ParseResult<Expr> {
+        let mut expr = self.and()?;
+        while self.eat("||") {
+            expr = Expr::Or(Box::new(expr), Box::new(self.and()?));
+        }
+        Ok(expr)
+    }
+
+    fn and(&mut self) -> ParseResult<Expr> {
+        let mut expr = self.unary()?;
+        while self.eat("&&") {
+            expr = Expr::And(Box::new(expr), Box::new(self.unary()?));
+        }
+        Ok(expr)
+    }
+
+    fn unary(&mut self) -> ParseResult<Expr> {
+        if self.eat("!") {
+            return Ok(Expr::Not(Box::new(self.unary()?)));
+        }
+        if self.eat("(") {
+            let expr = self.or()?;
+            if !self.eat(")") {
+                return self.error("expected ')'");
+            }
+            return Ok(expr);
+        }
+
+        self.skip_whitespaces();
+        let start = self.pos;
+        let word = self.word()?;
+
+        self.skip_whitespaces();
+        let op = OPERATORS
+            .iter()
+            .find(|(token, _)| self.rest().starts_with(token));
+        if let Some((token, op)) = op {
+            self.pos += token.len();
+            return self.condition(&word, start, *op);
+        }
+        if self.rest().starts_with("in") && !self.rest()[2..].starts_with(is_word_char) {
+            self.pos += 2;
+            return self.range(&word, start);
+        }
+
+        // Not followed by an operator, this is a list of GST_DEBUG thresholds
+        match parse_debug_spec(&word) {
+            Some(spec) => Ok(Expr::Debug(spec)),
+            None => self.error_at(start, "expected a condition"),
+        }
+    }
+
+    fn number(&mut self, field: NumberField) -> ParseResult<u64> {
+        self.skip_whitespaces();
+        let start = self.pos;
+        let word = self.word()?;
+        let value = match field {
+            NumberField::Ts => word.parse().ok().and_then(|t: ClockTime| t.nanoseconds()),
+            NumberField::Pid | NumberField::Line => word.parse().ok(),
+        };
+
+        match value {
+            Some(value) => Ok(value),
+            None if field == NumberField::Ts => self.error_at(start, "invalid time"),
+            None => self.error_at(start, "invalid number"),
+        }
+    }
+
+    fn condition(&mut self, field: &str, start: usize, op: Op) -> ParseResult<Expr> {
+        if field == "level" {
+            self.skip_whitespaces();
+            let value_start = self.pos;
+            let level = match self.word()?.parse() {
+                Ok(level) => level,
+                Err(()) => return self.error_at(value_start, "invalid level"),
+            };
+            return match op {
+                Op::Cmp(cmp) => Ok(Expr::Level(cmp, level)),
+                _ => self.error_at(start, "operator not supported for this field"),
+            };
+        }
+
+        if let Some(field) = number_field(field) {
+            return match op {
+                Op::Cmp(cmp) => Ok(Expr::Number(field, cmp, self.number(field)?)),
+                _ => self.error_at(start, "operator not supported for this field"),
+            };
+        }
+
+        let field = match text_field(field) {
+            Some(field) => field,
+            None => return self.error_at(start, "unknown field"),
+        };
+        let value = self.word()?;
+        match op {
+            Op::Cmp(cmp @ Cmp::Eq) | Op::Cmp(cmp @ Cmp::Ne) => Ok(Expr::Text(field, cmp, value)),
+            Op::Glob => Ok(Expr::Glob(field, value)),
+            Op::NotGlob => Ok(Expr::Not(Box::new(Expr::Glob(field, value)))),
+            Op::Cmp(_) => self.error_at(start, "operator not supported for this field"),
+        }
+    }
+
+    fn range(&mut self, field: &str, start: usize) -> ParseResult<Expr> {
+        let field = match number_field(field) {
+            Some(field) => field,
+            None if field == "level" || text_field(field).is_some() => {
+                return self.error_at(start, "operator not supported for this field")
+            }
+            None => return self.error_at(start, "unknown field"),
+        };
+
+        let min = if self.at_word() {
+            Some(self.number(field)?)
+        } else {
+            None
+        };
+        if !self.eat("..") {
+            return self.error("expected '..'");
+        }
+        let max = if self.at_word() {
+            Some(self.number(field)?)
+        } else {
+            None
+        };
+
+        Ok(Expr::Range(field, min, max))
+    }
+}
+
+fn number_field(name: &str) -> Option<NumberField> {
+    match name {
+        "ts" => Some(NumberField::Ts),
+        "pid" => Some(NumberField::Pid),
+        "line" => Some(NumberField::Line),
+        _ => None,
+    }
+}
+
+fn text_field(name: &str) -> Option<TextField> {
+    match name {
+        "category" => Some(TextField::Category),
+        "object" => Some(TextField::Object),
+        "element" => Some(TextField::Element),
+        "pad" => Some(TextField::Pad),
+        "thread" => Some(TextField::Thread),
+        "file" => Some(TextField::File),
+        "function" => Some(TextField::Function),
+        "message" => Some(TextField::Message),
+        _ => None,
+    }
+}
+
+impl Filter {
+    /// Parse a filter expression. An empty expression matches all the entries.
+    pub fn parse(s: &str) -> ParseResult<Filter> {
+        let mut parser = Parser { s, pos: 0 };
+        if parser.at_end() {
+            return Ok(Filter::default());
+        }
+
+        let expr = parser.or()?;
+        if !parser.at_end() {
+            return parser.error("unexpected characters");
+        }
+
+        Ok(Filter { expr: Some(expr) })
+    }
+
+    /// Combine with @other, accepting the entries matching both filters
+    pub fn and(self, other: Filter) -> Filter {
+        match (self.expr, other.expr) {
+            (Some(a), Some(b)) => Filter {
+                expr: Some(Expr::And(Box::new(a), Box::new(b))),
+            },
+            (a, b) => Filter { expr: a.or(b) },
+        }
+    }
+
+    /// Combine with @other, accepting the entries matching any of the filters
+    pub fn or(self, other: Filter) -> Filter {
+        match (self.expr, other.expr) {
+            (Some(a), Some(b)) => Filter {
+                expr: Some(Expr::Or(Box::new(a), Box::new(b))),
+            },
+            _ => Filter::default(),
+        }
+    }
+
+    /// Check if @entry is accepted by the filter
+    pub fn matches(&self, entry: &Entry) -> bool {
+        match &self.expr {
+            Some(expr) => expr.matches(entry),
+            None => true,
+        }
+    }
+}
+
+impl FromStr for Filter {
+    type Err = ParseFilterError;
+
+    fn from_str(s: &str) -> ParseResult<Filter> {
+        Filter::parse(s)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::parse;
+
+    const LOG: &str = "0:00:00.007773544  8874 0x558951015c00 INFO                GST_INIT gst.c:510:init_pre: Initializing\n\
+                       0:00:01.500000000  8874 0x558951015c00 WARN              v4l2src gstv4l2src.c:10:start:<v4l2src0> warning\n\
+                       0:00:02.000000000  8874 0x558951015c00 DEBUG            v4l2sink gstv4l2sink.c:20:show:<queue0:src> debug\n\
+                       0:00:06.000000000  8875 0x558951015c00 TRACE          GST_TRACER :0:: buffer, ts=(guint64)42;\n";
+
+    // The line numbers of the entries accepted by @filter
+    fn filtered(filter: &str) -> Vec<u32> {
+        let filter = Filter::parse(filter).unwrap();
+        parse(LOG.as_bytes())
+            .filter(|entry| filter.matches(entry))
+            .map(|entry| entry.line)
+            .collect()
+    }
+
+    #[test]
+    fn expressions() {
+        assert_eq!(filtered(""), vec![510, 10, 20, 0]);
+        assert_eq!(filtered("level<=WARN"), vec![10]);
+        assert_eq!(filtered("level == debug"), vec![20]);
+        assert_eq!(filtered("level>5"), vec![0]);
+        assert_eq!(filtered("category~\"v4l2*\""), vec![10, 20]);
+        assert_eq!(filtered("category!~v4l2*"), vec![510, 0]);
+        assert_eq!(filtered("category~v4l2????"), vec![20]);
+        assert_eq!(filtered("object==v4l2src0"), vec![10]);
+        assert_eq!(filtered("object!=v4l2src0"), vec![510, 20, 0]);
+        assert_eq!(filtered("element==queue0 && pad==src"), vec![20]);
+        assert_eq!(filtered("ts in 1s..5s"), vec![10, 20]);
+        assert_eq!(filtered("ts in 1.6s.."), vec![20, 0]);
+        assert_eq!(filtered("ts in ..0:00:01.5"), vec![510]);
+        assert_eq!(filtered("ts >= 2000ms"), vec![20, 0]);
+        assert_eq!(filtered("pid == 8875 || line == 510"), vec![510, 0]);
+        assert_eq!(filtered("!(level<=WARN) && message~\"*ts=*\""), vec![0]);
+        assert_eq!(
+            filtered("level<=WARN && category~\"v4l2*\" && object==\"v4l2src0\" && ts in 1s..5s"),
+            vec![10]
+        );
+    }
+
+    #[test]
+    fn debug_spec() {
+        assert_eq!(filtered("GST_TRACER:7"), vec![0]);
+        assert_eq!(filtered("*:4"), vec![510, 10]);
+        assert_eq!(filtered("3,v4l2*:5"), vec![10, 20]);
+        assert_eq!(filtered("v4l2*:DEBUG,v4l2sink:1"), vec![10]);
+        assert_eq!(filtered("*:9 && pid==8874"), vec![510, 10, 20]);
+    }
+
+    #[test]
+    fn combine() {
+        let filter = |s: &str| Filter::parse(s).unwrap();
+        let filtered = |filter: Filter| -> Vec<u32> {
+            parse(LOG.as_bytes())
+                .filter(|entry| filter.matches(entry))
+                .map(|entry| entry.line)
+                .collect()
+        };
+
+        assert_eq!(
+            filtered(filter("level<=WARN").or(filter("pid==8875"))),
+            vec![10, 0]
+        );
+        assert_eq!(
+            filtered(filter("category~v4l2*").and(filter("level==DEBUG"))),
+            vec![20]
+        );
+        assert_eq!(
+            filtered(Filter::default().and(filter("pid==8875"))),
+            vec![0]
+        );
+        assert_eq!(
+            filtered(Filter::default().or(filter("pid==8875"))),
+            vec![510, 10, 20, 0]
+        );
+    }
+
+    #[test]
+    fn errors() {
+        let error = |s: &str| Filter::parse(s).unwrap_err();
+
+        assert_eq!(
+            error("level<=LOUD"),
+            ParseFilterError {
+                position: 7,
+                reason: "invalid level"
+            }
+        );
+        assert_eq!(error("colour==red").reason, "unknown field");
+        assert_eq!(
+            error("category<v4l2").reason,
+            "operator not supported for this field"
+        );
+        assert_eq!(error("ts in 1s").reason, "expected '..'");
+        assert_eq!(error("ts > 5").reason, "invalid time");
+        assert_eq!(error("(level<=WARN").reason, "expected ')'");
+        assert_eq!(error("object==\"queue").reason, "unterminated string");
+        assert_eq!(error("level<=WARN pid==1").reason, "unexpected characters");
+        assert_eq!(error("GST_TRACER:LOUD").reason, "expected a condition");
+    }
+
+    #[test]
+    fn glob() {
+        assert!(glob_match("*", ""));
+        assert!(glob_match("v4l2*", "v4l2src"));
+        assert!(glob_match("*src*", "v4l2src0"));
+        assert!(glob_match("a*b*c", "aXbYbZc"));
+        assert!(!glob_match("a*b", "aXbY"));
+        assert!(!glob_match("?", ""));
+    }
+}
@@ -0,0 +1,587 @@
+use std::fs;
+use std::fs::File;
+use std::io;
+use std::io::{BufRead, BufReader, BufWriter, Read, Seek, SeekFrom, Write};
+use std::path::{Path, PathBuf};
+use std::time::UNIX_EPOCH;
+
+use memmap::Mmap;
+
+use crate::{strip_colors, try_parse, ClockTime, Entry, EntryRef, TIMESTAMP_START};
+
+const DEFAULT_INTERVAL: usize = 10_000;
+const SIDECAR_HEADER: &str = "gst-log-parser index 1";
+
+// Lines of the log starting at @offset, with the range of the running times of their entries
+#[derive(Debug, Clone, Copy, PartialEq)]
+struct Block {
+    offset: u64,
+    min_ts: u64,
+    max_ts: u64,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+struct Index {
+    // Size and modification time, in nanoseconds since the epoch, of the log when the index
+    // has been built
+    len: u64,
+    modified: u128,
+    interval: usize,
+    blocks: Vec<Block>,
+}
+
+/// A log file giving access to its entries by running time, or by position.
+///
+/// An index, mapping running times to positions in the file, is built the first time entries
+/// are retrieved by running time and is saved next to the log, with the `.idx` extension, so it
+/// can be re-used later. Only uncompressed logs in GStreamer's own format are supported.
+///
+/// The log is also mapped in memory so entries can be accessed in any order, using the offset
+/// in bytes of their first line.
+pub struct LogFile {
+    path: PathBuf,
+    interval: usize,
+    sidecar: bool,
+    index: Option<Index>,
+    // None if the file is empty as it cannot be mapped
+    mmap: Option<Mmap>,
+}
+
+impl LogFile {
+    /// Open the log at @path
+    pub fn open<P: AsRef<Path>>(path: P) -> io::Result<LogFile> {
+        let path = path.as_ref().to_path_buf();
+        let file = File::open(&path)?;
+        let mmap = if file.metadata()?.len() > 0 {
+            Some(unsafe { Mmap::map(&file)? })
+        } else {
+            None
+        };
+
+        Ok(LogFile {
+            path,
+            interval: DEFAULT_INTERVAL,
+            sidecar: true,
+            index: None,
+            mmap,
+        })
+    }
+
+    /// Set the number of lines between two positions saved in the index
+    pub fn interval(mut self, interval: usize) -> Self {
+        self.interval = interval.max(1);
+        self
+    }
+
+    /// Set if the index should be loaded from, and saved to, a file next to the log.
+    /// Failing to save it is not an error, the index is then only kept in memory.
+    pub fn sidecar(mut self, sidecar: bool) -> Self {
+        self.sidecar = sidecar;
+        self
+    }
+
+    /// Path of the file where the index is saved
+    pub fn sidecar_path(&self) -> PathBuf {
+        let mut path = self.path.clone().into_os_string();
+        path.push(".idx");
+        path.into()
+    }
+
+    /// Return the entries whose running time is between @start and @end, both included,
+    /// in the order they appear in the log. As the entries of different threads may not be
+    /// ordered by running time, all the parts of the log which may contain such entries
+    /// are parsed.
+    pub fn entries_between(
+        &mut self,
+        start: ClockTime,
+        end: ClockTime,
+    ) -> io::Result<impl Iterator<Item = Entry>> {
+        let start = start.nanoseconds().unwrap_or(0);
+        let end = end.nanoseconds().unwrap_or(u64::MAX);
+
+        let index = self.index()?;
+        let ranges = index.ranges(start, end);
+        let file = File::open(&self.path)?;
+
+        // Ranges are parsed while iterating so only one entry is kept in memory at a time
+        let entries = ranges.into_iter().flat_map(move |(offset, len)| {
+            // The log may have been truncated since the index has been built
+            let chunk = file
+                .try_clone()
+                .and_then(|mut chunk| chunk.seek(SeekFrom::Start(offset)).map(|_| chunk.take(len)));
+            chunk
+                .into_iter()
+                .flat_map(|chunk| try_parse(chunk).filter_map(Result::ok))
+        });
+
+        Ok(entries.filter(move |entry| match entry.ts.nanoseconds() {
+            Some(ts) => start <= ts && ts <= end,
+            None => false,
+        }))
+    }
+
+    /// Offset of the first entry, in the order of the log, whose running time is at least @ts.
+    /// Only the part of the log following the position found in the index is parsed.
+    pub fn seek(&mut self, ts: ClockTime) -> io::Result<Option<u64>> {
+        let start = match ts.nanoseconds() {
+            Some(ts) => match self.index()?.ranges(ts, u64::MAX).first() {
+                Some((offset, _)) => *offset,
+                None => return Ok(None),
+            },
+            None => 0,
+        };
+
+        Ok(self
+            .entries_from(start)
+            .find(|(_, entry)| entry.ts >= ts)
+            .map(|(offset, _)| offset))
+    }
+
+    fn data(&self) -> &[u8] {
+        match &self.mmap {
+            Some(mmap) => &mmap[..],
+            None => &[],
+        }
+    }
+
+    /// Size of the log, in bytes, when it has been opened
+    pub fn len(&self) -> u64 {
+        self.data().len() as u64
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.data().is_empty()
+    }
+
+    fn is_entry_start(&self, offset: usize) -> bool {
+        TIMESTAMP_START.is_match(&self.data()[offset..])
+    }
+
+    /// Offset of the first entry starting at, or after, @offset
+    pub fn next_entry(&self, offset: u64) -> Option<u64> {
+        let data = self.data();
+        let mut offset = (offset as usize).min(data.len());
+
+        // Move to the beginning of the next line, unless already there
+        if offset > 0 && data[offset - 1] != b'\n' {
+            offset += data[offset..].iter().position(|c| *c == b'\n')? + 1;
+        }
+
+        while offset < data.len() {
+            if self.is_entry_start(offset) {
+                return Some(offset as u64);
+            }
+            offset += data[offset..].iter().position(|c| *c == b'\n')? + 1;
+        }
+
+        None
+    }
+
+    /// Offset of the last entry starting before @offset
+    pub fn previous_entry(&self, offset: u64) -> Option<u64> {
+        let data = self.data();
+        let mut end = (offset as usize).min(data.len());
+
+        while end > 0 {
+            // Beginning of the line containing the byte preceding @end
+            let start = match data[..end - 1].iter().rposition(|c| *c == b'\n') {
+                Some(pos) => pos + 1,
+                None => 0,
+            };
+            if self.is_entry_start(start) {
+                return Some(start as u64);
+            }
+            end = start;
+        }
+
+        None
+    }
+
+    /// Parse the entry starting at @offset, returning it with the offset of the next entry,
+    /// or the size of the log if it's the last one
+    pub fn entry_at(&self, offset: u64) -> Option<(Entry, u64)> {
+        let data = self.data();
+        if offset >= self.len() || !self.is_entry_start(offset as usize) {
+            return None;
+        }
+
+        let line_end = match data[offset as usize..].iter().position(|c| *c == b'\n') {
+            Some(pos) => offset + pos as u64 + 1,
+            None => self.len(),
+        };
+        let end = self.next_entry(line_end).unwrap_or_else(|| self.len());
+        let entry = try_parse(&data[offset as usize..end as usize])
+            .next()?
+            .ok()?;
+
+        Some((entry, end))
+    }
+
+    /// Iterate over the entries starting at, or after, @offset, with their own offset
+    pub fn entries_from(&self, offset: u64) -> impl Iterator<Item = (u64, Entry)> + '_ {
+        let mut next = self.next_entry(offset);
+
+        std::iter::from_fn(move || loop {
+            let offset = next?;
+            match self.entry_at(offset) {
+                Some((entry, end)) => {
+                    next = Some(end).filter(|end| *end < self.len());
+                    return Some((offset, entry));
+                }
+                // Skip lines which can't be parsed
+                None => next = self.next_entry(offset + 1),
+            }
+        })
+    }
+
+    /// Iterate backward over the entries starting before @offset, with their own offset
+    pub fn entries_before(&self, offset: u64) -> impl Iterator<Item = (u64, Entry)> + '_ {
+        let mut previous = offset;
+
+        std::iter::from_fn(move || loop {
+            previous = self.previous_entry(previous)?;
+            if let Some((entry, _)) = self.entry_at(previous) {
+                return Some((previous, entry));
+            }
+        })
+    }
+
+    // The index, loaded or built if needed
+    fn index(&mut self) -> io::Result<&Index> {
+        let metadata = fs::metadata(&self.path)?;
+        let modified = metadata
+            .modified()
+            .ok()
+            .and_then(|time| time.duration_since(UNIX_EPOCH).ok())
+            .map(|d| d.as_nanos())
+            .unwrap_or(0);
+        let up_to_date = |index: &Index| {
+            index.len == metadata.len()
+                && index.modified == modified
+                && index.interval == self.interval
+        };
+
+        let valid = match &self.index {
+            Some(index) => up_to_date(index),
+            None => false,
+        };
+        if !valid {
+            let loaded = if self.sidecar {
+                Index::load(&self.sidecar_path()).filter(|index| up_to_date(index))
+            } else {
+                None
+            };
+
+            let index = match loaded {
+                Some(index) => index,
+                None => {
+                    let index = Index::build(&self.path, self.interval, metadata.len(), modified)?;
+                    if self.sidecar {
+                        // The index can be rebuilt next time
+                        let _ = index.save(&self.sidecar_path());
+                    }
+                    index
+                }
+            };
+
+            self.index = Some(index);
+        }
+
+        Ok(self.index.as_ref().unwrap())
+    }
+}
+
+impl Index {
+    fn build(path: &Path, interval: usize, len: u64, modified: u128) -> io::Result<Index> {
+        let mut reader = BufReader::new(File::open(path)?);
+        let mut blocks: Vec<Block> = Vec::new();
+        let mut buf = Vec::new();
+        let mut offset = 0;
+        // Start a block with the first entry
+        let mut lines = interval;
+
+        loop {
+            buf.clear();
+            let n = reader.read_until(b'\n', &mut buf)?;
+            if n == 0 {
+                break;
+            }
+
+            // Blocks always start with an entry so multi-line messages are not split
+            if TIMESTAMP_START.is_match(&buf) {
+                if lines >= interval {
+                    blocks.push(Block {
+                        offset,
+                        min_ts: u64::MAX,
+                        max_ts: 0,
+                    });
+                    lines = 0;
+                }
+
+                let line = String::from_utf8_lossy(&buf);
+                let line = strip_colors(line.trim_end());
+                let ts = EntryRef::new(&line)
+                    .ok()
+                    .and_then(|entry| entry.ts.nanoseconds());
+                if let (Some(block), Some(ts)) = (blocks.last_mut(), ts) {
+                    block.min_ts = block.min_ts.min(ts);
+                    block.max_ts = block.max_ts.max(ts);
+                }
+            }
+
+            lines += 1;
+            offset += n as u64;
+        }
+
+        Ok(Index {
+            len,
+            modified,
+            interval,
+            blocks,
+        })
+    }
+
+    // Offset and length of the parts of the log which may contain entries between @start and @end
+    fn ranges(&self, start: u64, end: u64) -> Vec<(u64, u64)> {
+        // Minimum running time of the entries from each block to the end of the log
+        let mut min_after = vec![u64::MAX; self.blocks.len() + 1];
+        for (i, block) in self.blocks.iter().enumerate().rev() {
+            min_after[i] = min_after[i + 1].min(block.min_ts);
+        }
+
+        let mut ranges: Vec<(u64, u64)> = Vec::new();
+        for (i, block) in self.blocks.iter().enumerate() {
+            if min_after[i] > end {
+                break;
+            }
+            if block.max_ts < start || block.min_ts > end {
+                continue;
+            }
+
+            let block_end = match self.blocks.get(i + 1) {
+                Some(next) => next.offset,
+                None => self.len,
+            };
+            match ranges.last_mut() {
+                // Merge with the previous block
+                Some((offset, len)) if *offset + *len == block.offset => *len = block_end - *offset,
+                _ => ranges.push((block.offset, block_end - block.offset)),
+            }
+        }
+
+        ranges
+    }
+
+    fn save(&self, path: &Path) -> io::Result<()> {
+        let mut out = BufWriter::new(File::create(path)?);
+
+        writeln!(out, "{}", SIDECAR_HEADER)?;
+        writeln!(out, "{} {} {}", self.len, self.modified, self.interval)?;
+        for block in &self.blocks {
+            writeln!(out, "{} {} {}", block.offset, block.min_ts, block.max_ts)?;
+        }
+
+        out.flush()
+    }
+
+    // Load a saved index, returning `None` if it's missing or invalid
+    fn load(path: &Path) -> Option<Index> {
+        let content = fs::read_to_string(path).ok()?;
+        let mut lines = content.lines();
+        if lines.next()? != SIDECAR_HEADER {
+            return None;
+        }
+
+        let mut header = lines.next()?.split(' ');
+        let len = header.next()?.parse().ok()?;
+        let modified = header.next()?.parse().ok()?;
+        let interval = header.next()?.parse().ok()?;
+
+        let blocks = lines
+            .map(|line| {
+                let mut split = line.split(' ');
+                Some(Block {
+                    offset: split.next()?.parse().ok()?,
+                    min_ts: split.next()?.parse().ok()?,
+                    max_ts: split.next()?.parse().ok()?,
+                })
+            })
+            .collect::<Option<Vec<Block>>>()?;
+
+        Some(Index {
+            len,
+            modified,
+            interval,
+            blocks,
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::parse;
+    use std::env;
+    use std::process;
+
+    fn between(entries: &[Entry], start: u64, end: u64) -> Vec<Entry> {
+        entries
+            .iter()
+            .filter(|e| {
+                let ts = e.ts.nanoseconds().unwrap();
+                start <= ts && ts <= end
+            })
+            .cloned()
+            .collect()
+    }
+
+    #[test]
+    fn entries_between() {
+        for path in &["test-logs/nocolor.log", "test-logs/color.log"] {
+            let f = File::open(path).expect("Failed to open log file");
+            let all: Vec<Entry> = parse(f).collect();
+
+            for interval in &[1, 2, 1000] {
+                let mut log = LogFile::open(path)
+                    .expect("Failed to open log file")
+                    .interval(*interval)
+                    .sidecar(false);
+
+                for (start, end) in &[
+                    (0, u64::MAX),
+                    (1_000_000_000, 100_000_000_000),
+                    (1_007_927_372, 1_007_927_372),
+                    (100_000_000_000, 200_000_000_000),
+                ] {
+                    let entries: Vec<Entry> = log
+                        .entries_between(
+                            ClockTime::from_nseconds(*start),
+                            ClockTime::from_nseconds(*end),
+                        )
+                        .unwrap()
+                        .collect();
+                    assert_eq!(entries, between(&all, *start, *end));
+                }
+            }
+        }
+    }
+
+    // Unique path as tests may run concurrently
+    fn temp_path(name: &str) -> PathBuf {
+        env::temp_dir().join(format!("gst-log-parser-{}-{}.log", process::id(), name))
+    }
+
+    #[test]
+    fn random_access() {
+        for path in &["test-logs/nocolor.log", "test-logs/color.log"] {
+            let expected: Vec<Entry> = parse(File::open(path).unwrap()).collect();
+            let mut log = LogFile::open(path).unwrap().sidecar(false);
+
+            let entries: Vec<(u64, Entry)> = log.entries_from(0).collect();
+            assert_eq!(
+                entries
+                    .iter()
+                    .map(|(_, e)| e.clone())
+                    .collect::<Vec<Entry>>(),
+                expected
+            );
+
+            let mut backward: Vec<(u64, Entry)> = log.entries_before(log.len()).collect();
+            backward.reverse();
+            assert_eq!(backward, entries);
+
+            for (i, (offset, entry)) in entries.iter().enumerate() {
+                assert_eq!(log.next_entry(*offset), Some(*offset));
+                assert_eq!(&log.entry_at(*offset).unwrap().0, entry);
+                assert_eq!(log.seek(entry.ts).unwrap(), Some(*offset));
+                if i > 0 {
+                    assert_eq!(log.previous_entry(*offset), Some(entries[i - 1].0));
+                    assert_eq!(log.next_entry(entries[i - 1].0 + 1), Some(*offset));
+                }
+            }
+
+            assert_eq!(log.seek(ClockTime::from_seconds(100_000)).unwrap(), None);
+            assert_eq!(log.entry_at(1), None);
+        }
+    }
+
+    #[test]
+    fn multi_line() {
+        let path = temp_path("multi-line");
+        fs::write(
+            &path,
+            "garbage\n\
+             0:00:01.000000000  1 0x1 INFO default a.c:1:f: a\n\
+             continued\n\
+             0:00:02.000000000  1 0x1 INFO default a.c:2:f: b\n",
+        )
+        .unwrap();
+
+        let log = LogFile::open(&path).unwrap();
+        let entries: Vec<(u64, String)> = log
+            .entries_from(0)
+            .map(|(offset, entry)| (offset, entry.message))
+            .collect();
+        assert_eq!(
+            entries,
+            vec![(8, "a\ncontinued".to_string()), (67, "b".to_string())]
+        );
+        assert_eq!(log.previous_entry(67), Some(8));
+        assert_eq!(log.previous_entry(8), None);
+
+        fs::remove_file(&path).unwrap();
+    }
+
+    #[test]
+    fn non_monotonic() {
+        let log = "0:00:01.000000000  1 0x1 INFO default a.c:1:f: a\n\
+                   0:00:02.000000000  1 0x1 INFO default a.c:2:f: b\n\
+                   0:00:05.000000000  1 0x2 INFO default a.c:3:f: c\n\
+                   multi-line\n\
+                   0:00:03.000000000  1 0x1 INFO default a.c:4:f: d\n\
+                   0:00:06.000000000  1 0x2 INFO default a.c:5:f: e\n\
+                   0:00:04.000000000  1 0x1 INFO default a.c:6:f: f\n\
+                   0:00:07.000000000  1 0x2 INFO default a.c:7:f: g\n";
+        let path = temp_path("non-monotonic");
+        fs::write(&path, log).expect("Failed to write log");
+
+        let lines = |log: &mut LogFile, start: u64, end: u64| -> Vec<u32> {
+            log.entries_between(ClockTime::from_seconds(start), ClockTime::from_seconds(end))
+                .unwrap()
+                .map(|entry| entry.line)
+                .collect()
+        };
+
+        let mut log = LogFile::open(&path).unwrap().interval(1);
+        assert_eq!(lines(&mut log, 3, 4), vec![4, 6]);
+        assert_eq!(lines(&mut log, 5, 5), vec![3]);
+        assert_eq!(lines(&mut log, 0, 2), vec![1, 2]);
+        assert_eq!(lines(&mut log, 8, 10), Vec::<u32>::new());
+        // First entry in the order of the log, not the earliest one
+        let seek = |log: &mut LogFile, ts: u64| {
+            let offset = log.seek(ClockTime::from_seconds(ts)).unwrap()?;
+            log.entry_at(offset).map(|(entry, _)| entry.line)
+        };
+        assert_eq!(seek(&mut log, 3), Some(3));
+        assert_eq!(seek(&mut log, 6), Some(5));
+        assert_eq!(seek(&mut log, 8), None);
+
+        // The index has been saved and is used by new instances
+        let sidecar = log.sidecar_path();
+        let index = Index::load(&sidecar).expect("Failed to load index");
+        assert_eq!(Some(&index), log.index.as_ref());
+        assert_eq!(index.blocks.len(), 7);
+        assert_eq!(index.blocks[2].offset, 98);
+
+        let mut log = LogFile::open(&path).unwrap().interval(1);
+        assert_eq!(lines(&mut log, 3, 4), vec![4, 6]);
+
+        // A different interval requires a new index
+        let mut log = LogFile::open(&path).unwrap().interval(3);
+        assert_eq!(lines(&mut log, 5, 6), vec![3, 5]);
+        assert_eq!(Index::load(&sidecar).unwrap().blocks.len(), 3);
+
+        fs::remove_file(&path).expect("Failed to remove log");
+        fs::remove_file(&sidecar).expect("Failed to remove index");
+    }
+}
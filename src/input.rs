@@ -0,0 +1,173 @@
+use std::fs;
+use std::fs::File;
+use std::io;
+use std::io::{BufRead, BufReader, Read};
+use std::path::Path;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Compression {
+    None,
+    Gzip,
+    Zstd,
+    Xz,
+}
+
+impl Compression {
+    fn detect(header: &[u8]) -> Self {
+        if header.starts_with(&[0x1f, 0x8b]) {
+            Compression::Gzip
+        } else if header.starts_with(&[0x28, 0xb5, 0x2f, 0xfd]) {
+            Compression::Zstd
+        } else if header.starts_with(&[0xfd, b'7', b'z', b'X', b'Z', 0x00]) {
+            Compression::Xz
+        } else {
+            Compression::None
+        }
+    }
+}
+
+#[cfg(not(all(feature = "gzip", feature = "zstd", feature = "xz")))]
+fn unsupported(format: &str, feature: &str) -> io::Error {
+    io::Error::new(
+        io::ErrorKind::Other,
+        format!(
+            "{} compressed log, rebuild with the '{}' feature to open it",
+            format, feature
+        ),
+    )
+}
+
+#[cfg(feature = "gzip")]
+fn gzip_decoder<R: BufRead + Send + 'static>(r: R) -> io::Result<Box<dyn Read + Send>> {
+    Ok(Box::new(flate2::bufread::MultiGzDecoder::new(r)))
+}
+
+#[cfg(not(feature = "gzip"))]
+fn gzip_decoder<R: BufRead + Send + 'static>(_r: R) -> io::Result<Box<dyn Read + Send>> {
+    Err(unsupported("gzip", "gzip"))
+}
+
+#[cfg(feature = "zstd")]
+fn zstd_decoder<R: BufRead + Send + 'static>(r: R) -> io::Result<Box<dyn Read + Send>> {
+    Ok(Box::new(zstd::stream::read::Decoder::with_buffer(r)?))
+}
+
+#[cfg(not(feature = "zstd"))]
+fn zstd_decoder<R: BufRead + Send + 'static>(_r: R) -> io::Result<Box<dyn Read + Send>> {
+    Err(unsupported("zstd", "zstd"))
+}
+
+#[cfg(feature = "xz")]
+fn xz_decoder<R: BufRead + Send + 'static>(r: R) -> io::Result<Box<dyn Read + Send>> {
+    Ok(Box::new(xz2::bufread::XzDecoder::new_multi_decoder(r)))
+}
+
+#[cfg(not(feature = "xz"))]
+fn xz_decoder<R: BufRead + Send + 'static>(_r: R) -> io::Result<Box<dyn Read + Send>> {
+    Err(unsupported("xz", "xz"))
+}
+
+/// Open the log file at @path, transparently decompressing it if it is
+/// compressed using gzip, zstd or xz. The standard input is used if @path is "-".
+pub fn open_log<P: AsRef<Path>>(path: P) -> io::Result<Box<dyn Read + Send>> {
+    let input: Box<dyn Read + Send> = if path.as_ref() == Path::new("-") {
+        Box::new(io::stdin())
+    } else {
+        Box::new(File::open(path)?)
+    };
+    let mut reader = BufReader::new(input);
+    let compression = Compression::detect(reader.fill_buf()?);
+
+    match compression {
+        Compression::None => Ok(Box::new(reader)),
+        Compression::Gzip => gzip_decoder(reader),
+        Compression::Zstd => zstd_decoder(reader),
+        Compression::Xz => xz_decoder(reader),
+    }
+}
+
+/// Check if the log at @path can be followed: only regular files may grow once their end
+/// has been reached, other inputs such as pipes are over then.
+pub fn can_follow<P: AsRef<Path>>(path: P) -> bool {
+    let path = if path.as_ref() == Path::new("-") {
+        Path::new("/dev/stdin")
+    } else {
+        path.as_ref()
+    };
+
+    match fs::metadata(path) {
+        Ok(metadata) => metadata.is_file(),
+        Err(_) => false,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::parse;
+
+    // Compress the test log using @encode and check that it is parsed as the original one
+    #[cfg(any(feature = "gzip", feature = "zstd", feature = "xz"))]
+    fn check_compressed<F>(name: &str, encode: F)
+    where
+        F: FnOnce(File) -> Box<dyn Read>,
+    {
+        use crate::Entry;
+        use std::env;
+        use std::fs;
+        use std::path::PathBuf;
+
+        let f = File::open("test-logs/nocolor.log").expect("Failed to open log file");
+        let expected: Vec<Entry> = parse(f).collect();
+
+        let path: PathBuf = env::temp_dir().join(format!("gst-log-parser-{}.log", name));
+        let f = File::open("test-logs/nocolor.log").expect("Failed to open log file");
+        let mut compressed = encode(f);
+        let mut out = File::create(&path).expect("Failed to create compressed log");
+        io::copy(&mut compressed, &mut out).expect("Failed to compress log");
+
+        let input = open_log(&path).expect("Failed to open compressed log");
+        let entries: Vec<Entry> = parse(input).collect();
+        fs::remove_file(&path).expect("Failed to remove compressed log");
+
+        assert_eq!(entries, expected);
+    }
+
+    #[test]
+    fn follow() {
+        assert!(can_follow("test-logs/color.log"));
+        assert!(!can_follow("test-logs"));
+        assert!(!can_follow("test-logs/nope.log"));
+    }
+
+    #[test]
+    fn uncompressed() {
+        let input = open_log("test-logs/color.log").expect("Failed to open log file");
+        assert_eq!(parse(input).count(), 15);
+    }
+
+    #[cfg(feature = "gzip")]
+    #[test]
+    fn gzip() {
+        check_compressed("gzip", |f| {
+            Box::new(flate2::read::GzEncoder::new(
+                f,
+                flate2::Compression::default(),
+            ))
+        });
+    }
+
+    #[cfg(feature = "zstd")]
+    #[test]
+    fn zstd() {
+        check_compressed("zstd", |f| {
+            Box::new(zstd::stream::read::Encoder::new(f, 0).expect("Failed to create encoder"))
+        });
+    }
+
+    #[cfg(feature = "xz")]
+    #[test]
+    fn xz() {
+        check_compressed("xz", |f| Box::new(xz2::read::XzEncoder::new(f, 6)));
+    }
+}
@@ -0,0 +1,210 @@
+use std::str;
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+
+use crate::{Entry, ParsingErrorKind, TIMESTAMP_START};
+
+/// Metadata of the systemd journal record an entry has been extracted from
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct JournalInfo {
+    /// Time at which the record has been received by the journal (`__REALTIME_TIMESTAMP`)
+    pub realtime: Option<SystemTime>,
+    /// Unit of the service which logged the record (`_SYSTEMD_UNIT`)
+    pub unit: Option<String>,
+    /// Process which logged the record (`_PID`)
+    pub pid: Option<u32>,
+    /// Syslog identifier, usually the process name (`SYSLOG_IDENTIFIER`)
+    pub identifier: Option<String>,
+}
+
+impl JournalInfo {
+    // Check if a record is the continuation of a message logged with @other
+    pub(crate) fn same_origin(&self, other: &JournalInfo) -> bool {
+        self.pid == other.pid && self.unit == other.unit
+    }
+}
+
+/// A journal record being read
+#[derive(Debug, Default)]
+pub(crate) struct Record {
+    pub(crate) info: JournalInfo,
+    pub(crate) message: Option<String>,
+}
+
+impl Record {
+    pub(crate) fn set_field(&mut self, name: &str, value: &[u8]) {
+        let text = || String::from_utf8_lossy(value).into_owned();
+
+        match name {
+            "MESSAGE" => self.message = Some(text()),
+            "__REALTIME_TIMESTAMP" => {
+                self.info.realtime = str::from_utf8(value)
+                    .ok()
+                    .and_then(|us| us.parse().ok())
+                    .map(|us| UNIX_EPOCH + Duration::from_micros(us))
+            }
+            "_SYSTEMD_UNIT" => self.info.unit = Some(text()),
+            "_PID" => self.info.pid = str::from_utf8(value).ok().and_then(|pid| pid.parse().ok()),
+            "SYSLOG_IDENTIFIER" => self.info.identifier = Some(text()),
+            _ => {}
+        }
+    }
+
+    pub(crate) fn is_empty(&self) -> bool {
+        self.message.is_none() && self.info == JournalInfo::default()
+    }
+}
+
+/// Check if @line starts a record of the journal export format (`journalctl -o export`)
+pub(crate) fn is_export(line: &str) -> bool {
+    line.starts_with("__CURSOR=") || line.starts_with("__REALTIME_TIMESTAMP=")
+}
+
+/// Check if @line is a record of the journal JSON format (`journalctl -o json`)
+pub(crate) fn is_json(line: &str) -> bool {
+    line.starts_with('{') && (line.contains("\"__CURSOR\"") || line.contains("\"MESSAGE\""))
+}
+
+#[cfg(feature = "json")]
+pub(crate) fn parse_json(line: &str) -> Result<Record, ParsingErrorKind> {
+    use serde_json::Value;
+
+    let fields = match serde_json::from_str(line) {
+        Ok(Value::Object(fields)) => fields,
+        _ => return Err(ParsingErrorKind::Unparsed),
+    };
+
+    let mut record = Record::default();
+    for (name, value) in fields.iter() {
+        match value {
+            Value::String(s) => record.set_field(name, s.as_bytes()),
+            // Fields which are not valid UTF-8 are exported as arrays of bytes
+            Value::Array(bytes) => {
+                let bytes: Vec<u8> = bytes
+                    .iter()
+                    .filter_map(|b| b.as_u64().map(|b| b as u8))
+                    .collect();
+                record.set_field(name, &bytes);
+            }
+            _ => {}
+        }
+    }
+
+    Ok(record)
+}
+
+#[cfg(not(feature = "json"))]
+pub(crate) fn parse_json(_line: &str) -> Result<Record, ParsingErrorKind> {
+    Err(ParsingErrorKind::Unsupported(
+        "journal JSON records, rebuild with the 'json' feature to parse them",
+    ))
+}
+
+/// Parse a GStreamer debug line from the message of a journal record
+pub(crate) fn parse_entry(info: &JournalInfo, message: &str) -> Result<Entry, ParsingErrorKind> {
+    if !TIMESTAMP_START.is_match(message.as_bytes()) {
+        return Err(ParsingErrorKind::Unparsed);
+    }
+
+    let mut entry = Entry::new(message).map_err(ParsingErrorKind::InvalidField)?;
+    entry.wallclock = entry.wallclock.or(info.realtime);
+    Ok(entry)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{try_parse, Wrapper};
+    use std::io;
+
+    const LINE: &str = "0:00:00.007773544  8874 0x558951015c00 INFO                GST_INIT gst.c:510:init_pre: Initializing";
+
+    fn check_info(entry: &Entry) {
+        match entry.wrapper {
+            Some(Wrapper::Journal(ref info)) => {
+                assert_eq!(info.unit, Some("camera.service".to_string()));
+                assert_eq!(info.pid, Some(8874));
+                assert_eq!(info.identifier, Some("gst-launch-1.0".to_string()));
+            }
+            _ => panic!("missing journal info"),
+        }
+        let since_epoch = entry.wallclock.unwrap().duration_since(UNIX_EPOCH).unwrap();
+        assert_eq!(since_epoch, Duration::from_micros(1_559_643_072_123_456));
+    }
+
+    #[test]
+    fn export() {
+        let mut log: Vec<u8> = Vec::new();
+        for message in &[LINE, "continued", "unrelated"] {
+            log.extend_from_slice(b"__CURSOR=s=739ad463348b4ceca5a9e69c95a3c93f\n");
+            log.extend_from_slice(b"__REALTIME_TIMESTAMP=1559643072123456\n");
+            log.extend_from_slice(b"_PID=8874\n");
+            log.extend_from_slice(b"SYSLOG_IDENTIFIER=gst-launch-1.0\n");
+            if *message == "unrelated" {
+                log.extend_from_slice(b"_SYSTEMD_UNIT=other.service\n");
+            } else {
+                log.extend_from_slice(b"_SYSTEMD_UNIT=camera.service\n");
+            }
+            log.extend_from_slice(format!("MESSAGE={}\n\n", message).as_bytes());
+        }
+        // Messages containing new lines are exported as binary fields
+        let message = format!("{}\nbinary\ncontinuation", LINE);
+        log.extend_from_slice(b"__REALTIME_TIMESTAMP=1559643072123456\n_PID=8874\n");
+        log.extend_from_slice(b"SYSLOG_IDENTIFIER=gst-launch-1.0\n_SYSTEMD_UNIT=camera.service\n");
+        log.extend_from_slice(b"MESSAGE\n");
+        log.extend_from_slice(&(message.len() as u64).to_le_bytes());
+        log.extend_from_slice(message.as_bytes());
+        log.extend_from_slice(b"\n");
+
+        let mut parsed = try_parse(&log[..]);
+
+        let entry = parsed.next().unwrap().unwrap();
+        assert_eq!(entry.message, "Initializing\ncontinued");
+        check_info(&entry);
+
+        let err = parsed.next().unwrap().unwrap_err();
+        assert_eq!(err.line, "unrelated");
+
+        let entry = parsed.next().unwrap().unwrap();
+        assert_eq!(entry.message, "Initializing\nbinary\ncontinuation");
+        check_info(&entry);
+
+        assert!(parsed.next().is_none());
+    }
+
+    #[test]
+    fn export_bogus_size() {
+        let mut log: Vec<u8> = Vec::new();
+        log.extend_from_slice(b"__REALTIME_TIMESTAMP=1559643072123456\n_PID=8874\n");
+        log.extend_from_slice(b"MESSAGE\n");
+        log.extend_from_slice(&u64::MAX.to_le_bytes());
+        log.extend_from_slice(b"short\n");
+
+        let mut parsed = try_parse(&log[..]);
+
+        let err = parsed.next().unwrap().unwrap_err();
+        assert_eq!(err.line, "MESSAGE");
+        match err.kind {
+            ParsingErrorKind::Io(ref err) => assert_eq!(err.kind(), io::ErrorKind::UnexpectedEof),
+            ref kind => panic!("wrong error: {:?}", kind),
+        }
+        assert!(parsed.next().is_none());
+    }
+
+    #[cfg(feature = "json")]
+    #[test]
+    fn json() {
+        let log = format!(
+            "{{\"__CURSOR\":\"s=739a\",\"__REALTIME_TIMESTAMP\":\"1559643072123456\",\"_PID\":\"8874\",\"SYSLOG_IDENTIFIER\":\"gst-launch-1.0\",\"_SYSTEMD_UNIT\":\"camera.service\",\"MESSAGE\":\"{}\"}}\n\
+             {{\"__CURSOR\":\"s=739b\",\"__REALTIME_TIMESTAMP\":\"1559643072123456\",\"_PID\":\"8874\",\"SYSLOG_IDENTIFIER\":\"gst-launch-1.0\",\"_SYSTEMD_UNIT\":\"camera.service\",\"MESSAGE\":[99,111,110,116,255]}}\n",
+            LINE
+        );
+
+        let mut parsed = try_parse(log.as_bytes());
+
+        let entry = parsed.next().unwrap().unwrap();
+        assert_eq!(entry.message, "Initializing\ncont\u{fffd}");
+        check_info(&entry);
+
+        assert!(parsed.next().is_none());
+    }
+}
@@ -1,24 +1,153 @@
+use std::borrow::Cow;
+use std::collections::VecDeque;
+use std::error;
 use std::fmt;
+use std::io;
 use std::io::BufRead;
 use std::io::BufReader;
-use std::io::Lines;
 use std::io::Read;
 use std::str;
-extern crate itertools;
-use itertools::join;
+use std::thread;
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
 
+#[cfg(feature = "gstreamer")]
 extern crate gstreamer as gst;
-use gst::{ClockTime, DebugLevel, Structure};
 
 #[macro_use]
 extern crate lazy_static;
 extern crate regex;
+use regex::bytes;
 use regex::Regex;
 
+mod clock_time;
+pub use clock_time::ClockTime;
+mod datetime;
+mod debug_level;
+pub use debug_level::DebugLevel;
+mod filter;
+pub use filter::{Filter, ParseFilterError};
+mod index;
+pub use index::LogFile;
+mod input;
+pub use input::{can_follow, open_log};
+mod journal;
+pub use journal::JournalInfo;
+mod logcat;
+pub use logcat::LogcatInfo;
+mod merge;
+pub use merge::{Alignment, Merge, MergeError, MergedEntry, Merger};
+mod message;
+pub use message::{MessageKind, RefOperation, State, StateChangeReturn};
+mod object;
+pub use object::LogObject;
+mod structure;
+pub use structure::{Caps, FromValue, ParseStructureError, Structure, Value};
+mod writer;
+pub use writer::Writer;
+#[cfg(feature = "serialize")]
+mod serialize;
+#[cfg(feature = "serialize")]
+pub use serialize::FlatEntry;
+mod parallel;
+pub use parallel::{parse_file_parallel, ParallelParser};
+
+/// The part of a log line which could not be parsed
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Field {
+    Timestamp,
+    Pid,
+    Thread,
+    Level,
+    Category,
+    Location,
+    Line,
+    Function,
+    Object,
+}
+
+impl fmt::Display for Field {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let s = match self {
+            Field::Timestamp => "timestamp",
+            Field::Pid => "pid",
+            Field::Thread => "thread",
+            Field::Level => "level",
+            Field::Category => "category",
+            Field::Location => "location",
+            Field::Line => "line",
+            Field::Function => "function",
+            Field::Object => "object",
+        };
+        f.write_str(s)
+    }
+}
+
 #[derive(Debug)]
-pub struct ParsingError;
+pub enum ParsingErrorKind {
+    /// Reading from the input failed
+    Io(io::Error),
+    /// The field is missing or could not be parsed
+    InvalidField(Field),
+    /// Text which is not part of any entry, reported in lenient mode and for messages
+    /// from other programs when parsing logcat or journal output
+    Unparsed,
+    /// The input uses a format which support has not been built
+    Unsupported(&'static str),
+}
 
 #[derive(Debug)]
+pub struct ParsingError {
+    /// 1-based number of the line in the input
+    pub line_number: usize,
+    /// The offending line, or part of line in lenient mode
+    pub line: String,
+    pub kind: ParsingErrorKind,
+}
+
+impl ParsingError {
+    fn new(line_number: usize, line: &str, kind: ParsingErrorKind) -> Self {
+        Self {
+            line_number,
+            line: line.to_string(),
+            kind,
+        }
+    }
+
+    /// The field which failed to parse, if any
+    pub fn field(&self) -> Option<Field> {
+        match self.kind {
+            ParsingErrorKind::InvalidField(field) => Some(field),
+            ParsingErrorKind::Io(_)
+            | ParsingErrorKind::Unparsed
+            | ParsingErrorKind::Unsupported(_) => None,
+        }
+    }
+}
+
+impl fmt::Display for ParsingError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match &self.kind {
+            ParsingErrorKind::Io(err) => write!(f, "line {}: {}", self.line_number, err),
+            ParsingErrorKind::InvalidField(field) => write!(
+                f,
+                "line {}: invalid {}: {:?}",
+                self.line_number, field, self.line
+            ),
+            ParsingErrorKind::Unparsed => write!(
+                f,
+                "line {}: unparsed text: {:?}",
+                self.line_number, self.line
+            ),
+            ParsingErrorKind::Unsupported(what) => {
+                write!(f, "line {}: unsupported {}", self.line_number, what)
+            }
+        }
+    }
+}
+
+impl error::Error for ParsingError {}
+
+#[derive(Debug, Clone, PartialEq)]
 pub struct Entry {
     pub ts: ClockTime,
     pub pid: u32,
@@ -30,9 +159,40 @@ pub struct Entry {
     pub function: String,
     pub message: String,
     pub object: Option<String>,
+    /// Absolute time of the entry, if the log contains wall-clock timestamps
+    pub wallclock: Option<SystemTime>,
+    /// Metadata added by the logging system the entry has been collected from, if any
+    pub wrapper: Option<Wrapper>,
+    /// Continuation lines of the message when using `Multiline::Split`
+    pub continuation: Vec<String>,
+}
+
+/// Logging system wrapping the GStreamer debug output
+#[derive(Debug, Clone, PartialEq)]
+pub enum Wrapper {
+    /// Android's logcat
+    Logcat(LogcatInfo),
+    /// The systemd journal
+    Journal(JournalInfo),
+}
+
+/// An entry borrowing its content from the parsed line
+#[derive(Debug, Clone, PartialEq)]
+pub struct EntryRef<'a> {
+    pub ts: ClockTime,
+    pub pid: u32,
+    pub thread: &'a str,
+    pub level: DebugLevel,
+    pub category: &'a str,
+    pub file: &'a str,
+    pub line: u32,
+    pub function: &'a str,
+    pub message: &'a str,
+    pub object: Option<&'a str>,
+    pub wallclock: Option<SystemTime>,
 }
 
-fn parse_debug_level(s: &str) -> Result<DebugLevel, ParsingError> {
+fn parse_debug_level(s: &str) -> Result<DebugLevel, Field> {
     match s {
         "ERROR" => Ok(DebugLevel::Error),
         "WARN" => Ok(DebugLevel::Warning),
@@ -42,155 +202,743 @@ fn parse_debug_level(s: &str) -> Result<DebugLevel, ParsingError> {
         "LOG" => Ok(DebugLevel::Log),
         "TRACE" => Ok(DebugLevel::Trace),
         "MEMDUMP" => Ok(DebugLevel::Memdump),
-        _ => Err(ParsingError),
+        _ => Err(Field::Level),
     }
 }
 
-fn parse_time(ts: &str) -> ClockTime {
+fn parse_time(ts: &str) -> Result<ClockTime, Field> {
+    let parse = |s: Option<&str>| -> Result<u64, Field> {
+        s.ok_or(Field::Timestamp)?
+            .parse()
+            .map_err(|_| Field::Timestamp)
+    };
+
     let mut split = ts.splitn(3, ':');
-    let h: u64 = split
-        .next()
-        .expect("missing hour")
-        .parse()
-        .expect("invalid hour");
-    let m: u64 = split
-        .next()
-        .expect("missing minute")
-        .parse()
-        .expect("invalid minute");
-    split = split.next().expect("missing second").splitn(2, '.');
-    let secs: u64 = split
-        .next()
-        .expect("missing second")
-        .parse()
-        .expect("invalid second");
-    let subsecs: u64 = split
-        .next()
-        .expect("missing sub second")
-        .parse()
-        .expect("invalid sub second");
+    let h = parse(split.next())?;
+    let m = parse(split.next())?;
+    let mut split = split.next().ok_or(Field::Timestamp)?.splitn(2, '.');
+    let secs = parse(split.next())?;
+    let subsecs = parse(split.next())?;
 
-    ClockTime::from_seconds(h * 60 * 60 + m * 60 + secs) + ClockTime::from_nseconds(subsecs)
+    h.checked_mul(60 * 60)
+        .and_then(|h| h.checked_add(m.checked_mul(60)?))
+        .and_then(|t| t.checked_add(secs))
+        .and_then(|t| t.checked_mul(1_000_000_000))
+        .and_then(|t| t.checked_add(subsecs))
+        .map(ClockTime::from_nseconds)
+        .ok_or(Field::Timestamp)
 }
 
-fn split_location(location: &str) -> (String, u32, String, Option<String>) {
+fn split_location(location: &str) -> Result<(&str, u32, &str, Option<&str>), Field> {
     let mut split = location.splitn(4, ':');
-    let file = split.next().expect("missing file");
+    let file = split.next().ok_or(Field::Location)?;
     let line = split
         .next()
-        .expect("missing line")
+        .ok_or(Field::Line)?
         .parse()
-        .expect("invalid line");
-    let function = split.next().expect("missing function");
-    let object = split.next().expect("missing object delimiter");
+        .map_err(|_| Field::Line)?;
+    let function = split.next().ok_or(Field::Function)?;
+    let object = split.next().ok_or(Field::Object)?;
     let object_name = {
         if !object.is_empty() {
-            let object = object
-                .to_string()
-                .trim_start_matches('<')
-                .trim_end_matches('>')
-                .to_string();
-
-            Some(object)
+            Some(object.trim_start_matches('<').trim_end_matches('>'))
         } else {
             None
         }
     };
 
-    (file.to_string(), line, function.to_string(), object_name)
+    Ok((file, line, function, object_name))
 }
 
-impl Entry {
-    fn new(line: &str) -> Entry {
-        // Strip color codes
-        lazy_static! {
-            static ref RE: Regex = Regex::new("\x1b\\[[0-9;]*m").unwrap();
-        }
-        let line = RE.replace_all(&line, "");
+// Running time since gst_init()
+const RUNNING_TIME: &str = r"\d+:\d{2}:\d{2}\.\d{9}\b";
+
+lazy_static! {
+    // Beginning of an entry, once color codes have been stripped
+    static ref HEADER: Regex = Regex::new(&format!(
+        r"(?:{dt} +(?:{rt} +)?|{rt} +)\d+ +\S+ +(ERROR|WARN|FIXME|INFO|DEBUG|LOG|TRACE|MEMDUMP) +\S+ ",
+        dt = datetime::DATETIME,
+        rt = RUNNING_TIME
+    ))
+    .unwrap();
+    // Lines starting with a timestamp, colored or not
+    static ref TIMESTAMP_START: bytes::Regex = bytes::Regex::new(&format!(
+        "^(\x1b\\[[0-9;]*m)*(?:{}|{})",
+        datetime::DATETIME,
+        RUNNING_TIME
+    ))
+    .unwrap();
+    static ref COLOR: Regex = Regex::new("\x1b\\[[0-9;]*m").unwrap();
+}
+
+fn strip_colors(line: &str) -> Cow<'_, str> {
+    COLOR.replace_all(line, "")
+}
+
+// Same as strip_colors() but re-using the allocation of @out
+fn strip_colors_into(line: &str, out: &mut String) {
+    out.clear();
+    let mut last = 0;
+    for m in COLOR.find_iter(line) {
+        out.push_str(&line[last..m.start()]);
+        last = m.end();
+    }
+    out.push_str(&line[last..]);
+}
 
-        let mut it = line.split(' ');
-        let ts = parse_time(it.next().expect("Missing ts"));
+impl<'a> EntryRef<'a> {
+    /// Parse a line which has already been stripped from its color codes.
+    ///
+    /// The running time may be prefixed or replaced by an absolute date and time, such as
+    /// `2019-06-04T10:11:12.123456789+02:00`. In the latter case `ts` is the time elapsed
+    /// since the Unix epoch.
+    pub fn new(line: &'a str) -> Result<EntryRef<'a>, Field> {
+        let (wallclock, rest) = datetime::split_datetime(line);
+        let mut it = rest.split(' ').peekable();
+        let ts = match (it.peek().map(|ts| parse_time(ts)), wallclock) {
+            (Some(Ok(ts)), _) => {
+                it.next();
+                ts
+            }
+            (_, Some(wallclock)) => {
+                let since_epoch = wallclock.duration_since(UNIX_EPOCH).unwrap_or_default();
+                ClockTime::from_seconds(since_epoch.as_secs())
+                    + ClockTime::from_nseconds(u64::from(since_epoch.subsec_nanos()))
+            }
+            (_, None) => return Err(Field::Timestamp),
+        };
         let mut it = it.skip_while(|x| x.is_empty());
         let pid = it
             .next()
-            .expect("Missing PID")
+            .ok_or(Field::Pid)?
             .parse()
-            .expect("Failed to parse PID");
+            .map_err(|_| Field::Pid)?;
         let mut it = it.skip_while(|x| x.is_empty());
-        let thread = it.next().expect("Missing thread").to_string();
+        let thread = it.next().ok_or(Field::Thread)?;
         let mut it = it.skip_while(|x| x.is_empty());
-        let level = parse_debug_level(it.next().expect("Missing level")).expect("Invalid level");
+        let level = parse_debug_level(it.next().ok_or(Field::Level)?)?;
         let mut it = it.skip_while(|x| x.is_empty());
-        let category = it.next().expect("Missing Category").to_string();
+        let category = it.next().ok_or(Field::Category)?;
         let mut it = it.skip_while(|x| x.is_empty());
-        let (file, line, function, object) = split_location(it.next().expect("Missing location"));
-        let message: String = join(it, " ");
+        let location = it.next().ok_or(Field::Location)?;
+        let (file, line_number, function, object) = split_location(location)?;
 
-        Entry {
+        // The message is everything following the space after the location
+        let location_end = location.as_ptr() as usize - line.as_ptr() as usize + location.len();
+        let message = line.get(location_end + 1..).unwrap_or("");
+
+        Ok(EntryRef {
             ts,
             pid,
             thread,
             level,
             category,
             file,
-            line,
+            line: line_number,
             function,
-            object,
             message,
+            object,
+            wallclock,
+        })
+    }
+
+    /// The object the entry has been logged for, if any
+    pub fn log_object(&self) -> Option<LogObject<'a>> {
+        self.object.map(LogObject::new)
+    }
+
+    /// Classify the message of the entry
+    pub fn message_kind(&self) -> MessageKind<'a> {
+        MessageKind::new(self.message)
+    }
+
+    pub fn to_owned(&self) -> Entry {
+        Entry {
+            ts: self.ts,
+            pid: self.pid,
+            thread: self.thread.to_string(),
+            level: self.level,
+            category: self.category.to_string(),
+            file: self.file.to_string(),
+            line: self.line,
+            function: self.function.to_string(),
+            message: self.message.to_string(),
+            object: self.object.map(str::to_string),
+            wallclock: self.wallclock,
+            wrapper: None,
+            continuation: Vec::new(),
+        }
+    }
+}
+
+impl Entry {
+    fn new(line: &str) -> Result<Entry, Field> {
+        let line = strip_colors(line);
+
+        EntryRef::new(&line).map(|entry| entry.to_owned())
+    }
+
+    fn add_continuation(&mut self, line: &str, multiline: Multiline) {
+        match multiline {
+            Multiline::Disabled => unreachable!(),
+            Multiline::Join => {
+                self.message.push('\n');
+                self.message.push_str(line);
+            }
+            Multiline::Split => self.continuation.push(line.to_string()),
         }
     }
 
-    pub fn message_to_struct(&self) -> Option<Structure> {
+    /// The object the entry has been logged for, if any
+    pub fn log_object(&self) -> Option<LogObject<'_>> {
+        self.object.as_ref().map(|object| LogObject::new(object))
+    }
+
+    /// Classify the message of the entry
+    pub fn message_kind(&self) -> MessageKind<'_> {
+        MessageKind::new(&self.message)
+    }
+
+    /// Parse the message as a serialized `GstStructure`, as logged by tracers
+    pub fn message_structure(&self) -> Option<Structure> {
         Structure::from_string(&self.message)
     }
+
+    /// Parse the message using GStreamer's own structure parser
+    #[cfg(feature = "gstreamer")]
+    pub fn message_to_struct(&self) -> Option<gst::Structure> {
+        gst::init().expect("Failed to initialize gst");
+
+        gst::Structure::from_string(&self.message)
+    }
 }
 
+/// Display the entry using the same layout as GStreamer, see `Writer`
 impl fmt::Display for Entry {
     fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
-        write!(
-            f,
-            "{}  {} {} {:?} {} {}:{}:{}:<{}> {}",
-            self.ts,
-            self.pid,
-            self.thread,
-            self.level,
-            self.category,
-            self.file,
-            self.line,
-            self.function,
-            self.object.clone().unwrap_or_else(|| "".to_string()),
-            self.message
-        )
+        writer::Canonical {
+            entry: self,
+            colors: false,
+        }
+        .fmt(f)
     }
 }
 
+/// How lines not starting with an entry header, such as caps or `MEMDUMP` dumps, are handled
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Multiline {
+    /// Each line is parsed as a separate entry
+    Disabled,
+    /// Continuation lines are appended to the message of the previous entry, separated by '\n'
+    Join,
+    /// Continuation lines are stored in the `continuation` field of the previous entry
+    Split,
+}
+
+/// Format of the input read by a `ParserIterator`
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum InputFormat {
+    /// Detected from the first line having a known format
+    Auto,
+    /// GStreamer debug output, as written on stderr
+    GStreamer,
+    /// Android logcat output, in the `threadtime`, `brief` or `long` format
+    Logcat,
+    /// systemd journal export format, as produced by `journalctl -o export`
+    JournalExport,
+    /// systemd journal JSON format, as produced by `journalctl -o json`.
+    /// Requires the `json` feature.
+    JournalJson,
+}
+
+impl InputFormat {
+    fn detect(line: &str) -> InputFormat {
+        let line = strip_colors(line);
+
+        if logcat::is_logcat(&line) {
+            InputFormat::Logcat
+        } else if journal::is_export(&line) {
+            InputFormat::JournalExport
+        } else if journal::is_json(&line) {
+            InputFormat::JournalJson
+        } else if TIMESTAMP_START.is_match(line.as_bytes()) {
+            InputFormat::GStreamer
+        } else {
+            InputFormat::Auto
+        }
+    }
+}
+
+/// Statistics about the input consumed by a `ParserIterator`
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub struct ParserStats {
+    /// Number of lines read
+    pub lines: usize,
+    /// Number of lines from which no entry could be parsed
+    pub skipped_lines: usize,
+    /// Number of bytes which are not part of any entry
+    pub skipped_bytes: usize,
+}
+
 pub struct ParserIterator<R: Read> {
-    lines: Lines<BufReader<R>>,
+    reader: BufReader<R>,
+    buf: Vec<u8>,
+    line_number: usize,
+    lenient: bool,
+    multiline: Multiline,
+    format: InputFormat,
+    // Header of the logcat entry being read, in the logcat `long` format
+    logcat_header: Option<LogcatInfo>,
+    // Record being read, in the journal export format
+    journal_record: journal::Record,
+    pending: VecDeque<Result<Entry, ParsingError>>,
+    eof: bool,
+    follow: bool,
+    // Waiting for more data in follow mode
+    waiting: bool,
+    stats: ParserStats,
 }
 
+// How often the input is checked for new data in follow mode
+const FOLLOW_INTERVAL: Duration = Duration::from_millis(100);
+
 impl<R: Read> ParserIterator<R> {
-    fn new(lines: Lines<BufReader<R>>) -> Self {
-        Self { lines }
+    fn new(reader: BufReader<R>) -> Self {
+        Self {
+            reader,
+            buf: Vec::new(),
+            line_number: 0,
+            lenient: false,
+            multiline: Multiline::Join,
+            format: InputFormat::Auto,
+            logcat_header: None,
+            journal_record: journal::Record::default(),
+            pending: VecDeque::new(),
+            eof: false,
+            follow: false,
+            waiting: false,
+            stats: ParserStats::default(),
+        }
+    }
+
+    /// In lenient mode entries are looked for anywhere in the line, not only at its start.
+    /// Text surrounding them, such as `g_print()` output or lines spliced by another thread,
+    /// is reported as `ParsingErrorKind::Unparsed` errors.
+    pub fn lenient(mut self, lenient: bool) -> Self {
+        self.lenient = lenient;
+        self
+    }
+
+    /// Set how multi-line messages are handled, default to `Multiline::Join`.
+    /// In lenient mode, only lines not containing any entry header are considered as continuations.
+    pub fn multiline(mut self, multiline: Multiline) -> Self {
+        self.multiline = multiline;
+        self
+    }
+
+    /// In follow mode, the iterator blocks once the end of the input has been reached,
+    /// waiting for more data to be written, as `tail -f` does.
+    /// The last entry is yielded before waiting so continuation lines written later
+    /// are not attached to it.
+    /// The end of a pipe is never detected in this mode, use `can_follow()` to check the input.
+    pub fn follow(mut self, follow: bool) -> Self {
+        self.follow = follow;
+        self
+    }
+
+    /// Set the format of the input, default to `InputFormat::Auto`.
+    pub fn format(mut self, format: InputFormat) -> Self {
+        self.format = format;
+        self
+    }
+
+    pub fn stats(&self) -> &ParserStats {
+        &self.stats
+    }
+
+    fn error(&self, line: &str, kind: ParsingErrorKind) -> Result<Entry, ParsingError> {
+        Err(ParsingError::new(self.line_number, line, kind))
+    }
+
+    fn parse_line(&mut self, line: &str) {
+        match Entry::new(line) {
+            Ok(entry) => self.pending.push_back(Ok(entry)),
+            Err(field) => {
+                self.stats.skipped_lines += 1;
+                self.stats.skipped_bytes += line.len();
+                let err = self.error(line, ParsingErrorKind::InvalidField(field));
+                self.pending.push_back(err);
+            }
+        }
+    }
+
+    fn parse_line_lenient(&mut self, line: &str) {
+        let line = strip_colors(line);
+
+        // Split the line so each part but the first starts with an entry header
+        let mut starts: Vec<usize> = HEADER.find_iter(&line).map(|m| m.start()).collect();
+        if starts.first() != Some(&0) {
+            starts.insert(0, 0);
+        }
+        starts.push(line.len());
+
+        let mut parsed = false;
+        for part in starts.windows(2) {
+            let text = &line[part[0]..part[1]];
+            if HEADER.is_match(text) {
+                if let Ok(entry) = Entry::new(text) {
+                    self.pending.push_back(Ok(entry));
+                    parsed = true;
+                    continue;
+                }
+            }
+
+            self.stats.skipped_bytes += text.len();
+            if !text.trim().is_empty() {
+                let err = self.error(text, ParsingErrorKind::Unparsed);
+                self.pending.push_back(err);
+            }
+        }
+
+        if !parsed {
+            self.stats.skipped_lines += 1;
+        }
+    }
+
+    fn is_continuation(&self, line: &str) -> bool {
+        if self.lenient {
+            !HEADER.is_match(&strip_colors(line))
+        } else {
+            !TIMESTAMP_START.is_match(line.as_bytes())
+        }
+    }
+
+    fn read_gst_line(&mut self, line: &str) {
+        let multiline = self.multiline;
+        let continuation = multiline != Multiline::Disabled && self.is_continuation(line);
+
+        match self.pending.back_mut() {
+            Some(Ok(entry)) if continuation => {
+                entry.add_continuation(&strip_colors(line), multiline);
+            }
+            _ => {
+                if self.lenient {
+                    self.parse_line_lenient(line);
+                } else {
+                    self.parse_line(line);
+                }
+            }
+        }
+    }
+
+    fn read_logcat_line(&mut self, line: &str) {
+        let stripped = strip_colors(line);
+
+        let (info, message) = match self.logcat_header.take() {
+            // Entries in the long format end with an empty line
+            Some(_) if stripped.is_empty() => return,
+            Some(header) => match logcat::parse_line(&stripped) {
+                Some(logcat::Line::LongHeader(info)) => {
+                    self.logcat_header = Some(info);
+                    return;
+                }
+                _ => {
+                    self.logcat_header = Some(header.clone());
+                    (header, &*stripped)
+                }
+            },
+            None => match logcat::parse_line(&stripped) {
+                Some(logcat::Line::Message(info, message)) => (info, message),
+                Some(logcat::Line::LongHeader(info)) => {
+                    self.logcat_header = Some(info);
+                    return;
+                }
+                None => {
+                    self.push_wrapped(line, Err(ParsingErrorKind::Unparsed));
+                    return;
+                }
+            },
+        };
+
+        let continuation = self.add_wrapped_continuation(message, |wrapper| match wrapper {
+            Wrapper::Logcat(previous) => previous.same_origin(&info),
+            _ => false,
+        });
+
+        if !continuation {
+            let entry = logcat::parse_entry(info, message);
+            self.push_wrapped(line, entry);
+        }
+    }
+
+    fn read_journal_export_line(&mut self, line: &str) {
+        if line.is_empty() {
+            self.flush_journal_record();
+            return;
+        }
+
+        match line.find('=') {
+            Some(pos) => self
+                .journal_record
+                .set_field(&line[..pos], &line.as_bytes()[pos + 1..]),
+            // Binary field: its size as a 64 bits little endian integer followed by the data
+            None => match self.read_binary_field() {
+                Ok(value) => self.journal_record.set_field(line, &value),
+                Err(err) => {
+                    let err = ParsingError::new(self.line_number, line, ParsingErrorKind::Io(err));
+                    self.pending.push_back(Err(err));
+                }
+            },
+        }
+    }
+
+    fn read_binary_field(&mut self) -> io::Result<Vec<u8>> {
+        let mut size = [0; 8];
+        self.reader.read_exact(&mut size)?;
+        // The size comes from the input so the data is not pre-allocated, a corrupted log
+        // could make it huge
+        let len = u64::from_le_bytes(size);
+        let mut value = Vec::new();
+        (&mut self.reader).take(len).read_to_end(&mut value)?;
+        if (value.len() as u64) < len {
+            return Err(io::Error::new(
+                io::ErrorKind::UnexpectedEof,
+                "truncated binary field",
+            ));
+        }
+        // Trailing new line
+        self.reader.read_exact(&mut size[..1])?;
+
+        Ok(value)
+    }
+
+    fn flush_journal_record(&mut self) {
+        let record = std::mem::take(&mut self.journal_record);
+        if !record.is_empty() {
+            self.read_journal_record(record);
+        }
+    }
+
+    fn read_journal_json_line(&mut self, line: &str) {
+        match journal::parse_json(line) {
+            Ok(record) => self.read_journal_record(record),
+            Err(kind) => self.push_wrapped(line, Err(kind)),
+        }
+    }
+
+    fn read_journal_record(&mut self, record: journal::Record) {
+        let message = match record.message {
+            Some(message) => message,
+            None => return,
+        };
+        let message = strip_colors(&message);
+        let info = record.info;
+
+        // Messages may contain several lines
+        for message in message.split('\n') {
+            let continuation = self.add_wrapped_continuation(message, |wrapper| match wrapper {
+                Wrapper::Journal(previous) => previous.same_origin(&info),
+                _ => false,
+            });
+
+            if !continuation {
+                let entry = journal::parse_entry(&info, message).map(|mut entry| {
+                    entry.wrapper = Some(Wrapper::Journal(info.clone()));
+                    entry
+                });
+                self.push_wrapped(message, entry);
+            }
+        }
+    }
+
+    // Attach @message to the previous entry if it continues a message collected from the
+    // same origin, as checked by @same_origin
+    fn add_wrapped_continuation<F>(&mut self, message: &str, same_origin: F) -> bool
+    where
+        F: Fn(&Wrapper) -> bool,
+    {
+        let multiline = self.multiline;
+        if multiline == Multiline::Disabled || TIMESTAMP_START.is_match(message.as_bytes()) {
+            return false;
+        }
+
+        if let Some(Ok(entry)) = self.pending.back_mut() {
+            let same = match entry.wrapper {
+                Some(ref wrapper) => same_origin(wrapper),
+                None => false,
+            };
+            if same {
+                entry.add_continuation(message, multiline);
+                return true;
+            }
+        }
+
+        false
+    }
+
+    fn push_wrapped(&mut self, line: &str, entry: Result<Entry, ParsingErrorKind>) {
+        match entry {
+            Ok(entry) => self.pending.push_back(Ok(entry)),
+            Err(kind) => {
+                self.stats.skipped_lines += 1;
+                self.stats.skipped_bytes += line.len();
+                let err = self.error(line, kind);
+                self.pending.push_back(err);
+            }
+        }
+    }
+
+    fn read_line(&mut self) {
+        // @buf may already contain the beginning of the line in follow mode
+        loop {
+            match self.reader.read_until(b'\n', &mut self.buf) {
+                Err(err) => {
+                    // Don't keep on failing on a broken input
+                    self.eof = true;
+                    let err =
+                        ParsingError::new(self.line_number + 1, "", ParsingErrorKind::Io(err));
+                    self.pending.push_back(Err(err));
+                    return;
+                }
+                Ok(_) if self.buf.ends_with(b"\n") => break,
+                Ok(_) if !self.follow => {
+                    if self.buf.is_empty() {
+                        self.eof = true;
+                        // The last record may not be followed by an empty line
+                        self.flush_journal_record();
+                        return;
+                    }
+                    // Last line is not terminated
+                    break;
+                }
+                Ok(_) => {
+                    if !self.pending.is_empty() {
+                        self.waiting = true;
+                        return;
+                    }
+                    thread::sleep(FOLLOW_INTERVAL);
+                }
+            }
+        }
+        self.waiting = false;
+
+        self.line_number += 1;
+        self.stats.lines += 1;
+
+        // Logs may contain random binary data, don't give up on them
+        let buf = std::mem::take(&mut self.buf);
+        let line = String::from_utf8_lossy(&buf);
+        let line = line.trim_end_matches(&['\n', '\r'][..]);
+
+        if self.format == InputFormat::Auto {
+            self.format = InputFormat::detect(line);
+        }
+
+        match self.format {
+            InputFormat::Logcat => self.read_logcat_line(line),
+            InputFormat::JournalExport => self.read_journal_export_line(line),
+            InputFormat::JournalJson => self.read_journal_json_line(line),
+            _ => self.read_gst_line(line),
+        }
+        self.buf = buf;
+        self.buf.clear();
+    }
+
+    fn has_complete_item(&self) -> bool {
+        match self.pending.len() {
+            0 => false,
+            // The last entry may still receive continuation lines
+            1 => self.multiline == Multiline::Disabled || self.pending[0].is_err() || self.waiting,
+            _ => true,
+        }
     }
 }
 
 impl<R: Read> Iterator for ParserIterator<R> {
-    type Item = Entry;
+    type Item = Result<Entry, ParsingError>;
 
-    fn next(&mut self) -> Option<Entry> {
-        match self.lines.next() {
-            None => None,
-            Some(line) => Some(Entry::new(&line.unwrap())),
+    fn next(&mut self) -> Option<Result<Entry, ParsingError>> {
+        while !self.eof && !self.has_complete_item() {
+            self.read_line();
         }
+
+        self.pending.pop_front()
     }
 }
 
-pub fn parse<R: Read>(r: R) -> ParserIterator<R> {
-    gst::init().expect("Failed to initialize gst");
+/// Parse the log, reporting lines which cannot be parsed as errors
+pub fn try_parse<R: Read>(r: R) -> ParserIterator<R> {
+    ParserIterator::new(BufReader::new(r))
+}
+
+/// Parse the log, silently skipping lines which cannot be parsed
+pub fn parse<R: Read>(r: R) -> impl Iterator<Item = Entry> {
+    try_parse(r).filter_map(Result::ok)
+}
+
+/// Parser lending borrowed entries one at a time, re-using the same buffers for each line.
+/// Unlike `ParserIterator` it does not support lenient mode nor multi-line messages.
+pub struct StreamingParser<R: Read> {
+    reader: BufReader<R>,
+    buf: Vec<u8>,
+    lossy: String,
+    stripped: String,
+    line_number: usize,
+}
+
+impl<R: Read> StreamingParser<R> {
+    fn new(reader: BufReader<R>) -> Self {
+        Self {
+            reader,
+            buf: Vec::new(),
+            lossy: String::new(),
+            stripped: String::new(),
+            line_number: 0,
+        }
+    }
+
+    /// Parse the next line, the returned entry is only valid until the next call
+    pub fn next_entry(&mut self) -> Option<Result<EntryRef<'_>, ParsingError>> {
+        self.buf.clear();
+        match self.reader.read_until(b'\n', &mut self.buf) {
+            Err(err) => {
+                return Some(Err(ParsingError::new(
+                    self.line_number + 1,
+                    "",
+                    ParsingErrorKind::Io(err),
+                )));
+            }
+            Ok(0) => return None,
+            Ok(_) => {}
+        }
+        self.line_number += 1;
+
+        let line = match str::from_utf8(&self.buf) {
+            Ok(line) => line,
+            Err(_) => {
+                self.lossy = String::from_utf8_lossy(&self.buf).into_owned();
+                &self.lossy
+            }
+        };
+        let line = line.trim_end_matches(&['\n', '\r'][..]);
+
+        let line = if line.contains('\x1b') {
+            strip_colors_into(line, &mut self.stripped);
+            &self.stripped
+        } else {
+            line
+        };
 
-    let file = BufReader::new(r);
+        let line_number = self.line_number;
+        Some(EntryRef::new(line).map_err(|field| {
+            ParsingError::new(line_number, line, ParsingErrorKind::InvalidField(field))
+        }))
+    }
+}
 
-    ParserIterator::new(file.lines())
+/// Parse the log without allocating for each entry
+pub fn parse_streaming<R: Read>(r: R) -> StreamingParser<R> {
+    StreamingParser::new(BufReader::new(r))
 }
 
 #[cfg(test)]
@@ -245,4 +993,207 @@ mod tests {
 
         assert_eq!(parsed.count(), 14);
     }
+
+    #[test]
+    fn invalid_lines() {
+        let log = "0:00:00.007773544  8874 0x558951015c00 INFO                GST_INIT gst.c:510:init_pre: first\n\
+                   0:00:00.007927372  8874 0x558951015c00 DEBUG\n\
+                   0:00:xx.008032206  8874 0x558951015c00 INFO                GST_INIT gst.c:510:init_pre: bad ts\n\
+                   0:00:00.008043265  8874 0x558951015c00 INFO                GST_INIT gst.c:nope:init_pre: bad line\n\
+                   99999999999999999:00:00.000000000  8874 0x558951015c00 INFO                GST_INIT gst.c:510:init_pre: overflow\n\
+                   0:00:00.008067915  8874 0x558951015c00 INFO                GST_INIT gst.c:510:init_pre: last\n";
+
+        let mut parsed = try_parse(log.as_bytes()).multiline(Multiline::Disabled);
+        assert_eq!(parsed.next().unwrap().unwrap().message, "first");
+
+        let err = parsed.next().unwrap().unwrap_err();
+        assert_eq!(err.line_number, 2);
+        assert_eq!(err.field(), Some(Field::Category));
+        assert!(err.line.ends_with("DEBUG"));
+
+        let err = parsed.next().unwrap().unwrap_err();
+        assert_eq!(err.line_number, 3);
+        assert_eq!(err.field(), Some(Field::Timestamp));
+
+        let err = parsed.next().unwrap().unwrap_err();
+        assert_eq!(err.line_number, 4);
+        assert_eq!(err.field(), Some(Field::Line));
+
+        let err = parsed.next().unwrap().unwrap_err();
+        assert_eq!(err.line_number, 5);
+        assert_eq!(err.field(), Some(Field::Timestamp));
+
+        assert_eq!(parsed.next().unwrap().unwrap().message, "last");
+        assert!(parsed.next().is_none());
+
+        let messages: Vec<String> = try_parse(log.as_bytes())
+            .multiline(Multiline::Disabled)
+            .filter_map(Result::ok)
+            .map(|e| e.message)
+            .collect();
+        assert_eq!(messages, vec!["first", "last"]);
+    }
+
+    #[test]
+    fn lenient() {
+        let log = "0:00:00.007773544  8874 0x558951015c00 INFO                GST_INIT gst.c:510:init_pre: first\n\
+                   Hello from g_print()\n\
+                   junk0:00:00.007927372  8874 0x558951015c00 INFO                GST_INIT gst.c:510:init_pre: second\n\
+                   0:00:00.008032206  8874 0x558951015c00 INFO  GST_INIT gst.c:510:init_pre: th\
+                   0:00:00.008043265  8874 0x558951015c01 INFO                GST_INIT gst.c:510:init_pre: fourth\n";
+
+        let mut parsed = try_parse(log.as_bytes())
+            .lenient(true)
+            .multiline(Multiline::Disabled);
+        assert_eq!(parsed.next().unwrap().unwrap().message, "first");
+
+        let err = parsed.next().unwrap().unwrap_err();
+        assert_eq!(err.line_number, 2);
+        assert_eq!(err.line, "Hello from g_print()");
+
+        let err = parsed.next().unwrap().unwrap_err();
+        assert_eq!(err.line_number, 3);
+        assert_eq!(err.line, "junk");
+        assert_eq!(parsed.next().unwrap().unwrap().message, "second");
+
+        assert_eq!(parsed.next().unwrap().unwrap().message, "th");
+        let entry = parsed.next().unwrap().unwrap();
+        assert_eq!(entry.message, "fourth");
+        assert_eq!(entry.thread, "0x558951015c01");
+        assert!(parsed.next().is_none());
+
+        assert_eq!(
+            *parsed.stats(),
+            ParserStats {
+                lines: 4,
+                skipped_lines: 1,
+                skipped_bytes: 24,
+            }
+        );
+    }
+
+    #[test]
+    fn multiline() {
+        let log = "stray line\n\
+                   0:00:00.007773544  8874 0x558951015c00 MEMDUMP           GST_BUFFER gstbuffer.c:1:dump: dumping\n\
+                   00000000: 00 01 02 03  ....\n\
+                   00000010: 04 05        ..\n\
+                   0:00:00.007927372  8874 0x558951015c00 INFO                GST_INIT gst.c:510:init_pre: single\n";
+
+        let mut parsed = try_parse(log.as_bytes());
+        let err = parsed.next().unwrap().unwrap_err();
+        assert_eq!(err.line_number, 1);
+        let entry = parsed.next().unwrap().unwrap();
+        assert_eq!(
+            entry.message,
+            "dumping\n00000000: 00 01 02 03  ....\n00000010: 04 05        .."
+        );
+        assert!(entry.continuation.is_empty());
+        assert_eq!(parsed.next().unwrap().unwrap().message, "single");
+        assert!(parsed.next().is_none());
+
+        let mut parsed = try_parse(log.as_bytes())
+            .multiline(Multiline::Split)
+            .filter_map(Result::ok);
+        let entry = parsed.next().unwrap();
+        assert_eq!(entry.message, "dumping");
+        assert_eq!(
+            entry.continuation,
+            vec!["00000000: 00 01 02 03  ....", "00000010: 04 05        .."]
+        );
+        let entry = parsed.next().unwrap();
+        assert_eq!(entry.message, "single");
+        assert!(entry.continuation.is_empty());
+    }
+
+    #[test]
+    fn streaming() {
+        for path in &["test-logs/nocolor.log", "test-logs/color.log"] {
+            let f = File::open(path).expect("Failed to open log file");
+            let mut expected = parse(f);
+
+            let f = File::open(path).expect("Failed to open log file");
+            let mut parsed = parse_streaming(f);
+            while let Some(entry) = parsed.next_entry() {
+                let entry = entry.expect("Failed to parse entry").to_owned();
+                assert_eq!(Some(entry), expected.next());
+            }
+            assert!(expected.next().is_none());
+        }
+
+        let mut parsed = parse_streaming("0:00:00.007773544  8874 0x558951015c00 INFO  GST_INIT gst.c:510:init_pre:<obj>  two  spaces \n".as_bytes());
+        let entry = parsed.next_entry().unwrap().unwrap();
+        assert_eq!(entry.object, Some("obj"));
+        assert_eq!(entry.message, " two  spaces ");
+    }
+
+    // Input returning its parts one by one, reaching the end of the stream after each of them
+    struct GrowingInput {
+        parts: VecDeque<&'static str>,
+        at_end: bool,
+    }
+
+    impl Read for GrowingInput {
+        fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+            self.at_end = !self.at_end;
+            if self.at_end {
+                return Ok(0);
+            }
+
+            match self.parts.pop_front() {
+                None => Ok(0),
+                Some(part) => {
+                    buf[..part.len()].copy_from_slice(part.as_bytes());
+                    Ok(part.len())
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn follow() {
+        let input = GrowingInput {
+            parts: vec![
+                "0:00:00.007773544  8874 0x558951015c00 INFO                GST_INIT gst.c:510:init_pre: first\n",
+                "0:00:00.007927372  8874 0x558951015c00 INFO                GST_INIT gst.",
+                "c:510:init_pre: second\n",
+                "0:00:00.008032206  8874 0x558951015c00 INFO                GST_INIT gst.c:510:init_pre: third\n",
+            ]
+            .into_iter()
+            .collect(),
+            at_end: false,
+        };
+
+        let messages: Vec<String> = try_parse(input)
+            .follow(true)
+            .take(3)
+            .map(|e| e.unwrap().message)
+            .collect();
+        assert_eq!(messages, vec!["first", "second", "third"]);
+    }
+
+    #[test]
+    fn wallclock() {
+        let log = "2019-06-04T10:11:12.000001Z 0:00:00.007773544  8874 0x558951015c00 INFO                GST_INIT gst.c:510:init_pre: prefixed\n\
+                   continued\n\
+                   \x1b[32m[2019-06-04 12:11:12.5+02:00]\x1b[00m  8874 0x558951015c00 INFO                GST_INIT gst.c:510:init_pre: absolute\n";
+
+        let mut parsed = parse(log.as_bytes());
+
+        let entry = parsed.next().unwrap();
+        assert_eq!(entry.ts.nanoseconds().unwrap(), 7773544);
+        assert_eq!(entry.pid, 8874);
+        assert_eq!(entry.message, "prefixed\ncontinued");
+        let since_epoch = entry.wallclock.unwrap().duration_since(UNIX_EPOCH).unwrap();
+        assert_eq!(since_epoch, Duration::new(1_559_643_072, 1000));
+
+        let entry = parsed.next().unwrap();
+        assert_eq!(entry.ts.nanoseconds().unwrap(), 1_559_643_072_500_000_000);
+        assert_eq!(entry.pid, 8874);
+        assert_eq!(entry.message, "absolute");
+        let since_epoch = entry.wallclock.unwrap().duration_since(UNIX_EPOCH).unwrap();
+        assert_eq!(since_epoch, Duration::new(1_559_643_072, 500_000_000));
+
+        assert!(parsed.next().is_none());
+    }
 }
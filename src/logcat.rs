@@ -0,0 +1,267 @@
+use std::time::SystemTime;
+
+use regex::Regex;
+
+use crate::datetime;
+use crate::{
+    parse_time, split_location, DebugLevel, Entry, Field, ParsingErrorKind, Wrapper,
+    TIMESTAMP_START,
+};
+
+/// Metadata added by Android's logcat to each line
+#[derive(Debug, Clone, PartialEq)]
+pub struct LogcatInfo {
+    /// Timestamp as printed by logcat, absent in the `brief` format
+    pub time: Option<String>,
+    pub pid: u32,
+    /// Thread id, absent in the `brief` format
+    pub tid: Option<u32>,
+    /// Priority letter: V, D, I, W, E, F or S
+    pub priority: char,
+    pub tag: String,
+}
+
+// Tag prefix used by GStreamer's own logcat debug handler, followed by the category name
+const GST_TAG_PREFIX: &str = "GStreamer+";
+
+lazy_static! {
+    // 06-04 10:11:12.345  1234  1250 I GStreamer+GST_INIT: message
+    static ref THREADTIME: Regex = Regex::new(
+        r"^((?:\d{4}-)?\d{2}-\d{2} \d{2}:\d{2}:\d{2}\.\d+)\s+(\d+)\s+(\d+) ([VDIWEFS]) (.*?)\s*: ?(.*)$"
+    )
+    .unwrap();
+    // I/GStreamer+GST_INIT( 1234): message
+    static ref BRIEF: Regex = Regex::new(r"^([VDIWEFS])/(.*?)\(\s*(\d+)\): ?(.*)$").unwrap();
+    // [ 06-04 10:11:12.345  1234: 1250 I/GStreamer+GST_INIT ]
+    static ref LONG: Regex = Regex::new(
+        r"^\[ ((?:\d{4}-)?\d{2}-\d{2} \d{2}:\d{2}:\d{2}\.\d+)\s+(\d+):\s*(\d+) ([VDIWEFS])/(.*?)\s*\]$"
+    )
+    .unwrap();
+}
+
+/// A line of logcat output, once color codes have been stripped
+#[derive(Debug)]
+pub(crate) enum Line<'a> {
+    /// A line carrying a message
+    Message(LogcatInfo, &'a str),
+    /// Header of an entry in the `long` format, its message is on the following lines
+    LongHeader(LogcatInfo),
+}
+
+fn info(time: Option<&str>, pid: &str, tid: Option<&str>, priority: &str, tag: &str) -> LogcatInfo {
+    LogcatInfo {
+        time: time.map(str::to_string),
+        pid: pid.parse().unwrap_or(0),
+        tid: tid.and_then(|tid| tid.parse().ok()),
+        priority: priority.chars().next().unwrap_or('V'),
+        tag: tag.to_string(),
+    }
+}
+
+/// Parse a line, returning `None` if it is not logcat output or one of logcat's own messages,
+/// such as `--------- beginning of main`.
+pub(crate) fn parse_line(line: &str) -> Option<Line<'_>> {
+    if let Some(caps) = THREADTIME.captures(line) {
+        let info = info(Some(&caps[1]), &caps[2], Some(&caps[3]), &caps[4], &caps[5]);
+        Some(Line::Message(info, caps.get(6).unwrap().as_str()))
+    } else if let Some(caps) = BRIEF.captures(line) {
+        let info = info(None, &caps[3], None, &caps[1], &caps[2]);
+        Some(Line::Message(info, caps.get(4).unwrap().as_str()))
+    } else {
+        LONG.captures(line).map(|caps| {
+            Line::LongHeader(info(
+                Some(&caps[1]),
+                &caps[2],
+                Some(&caps[3]),
+                &caps[4],
+                &caps[5],
+            ))
+        })
+    }
+}
+
+/// Check if @line looks like logcat output
+pub(crate) fn is_logcat(line: &str) -> bool {
+    parse_line(line).is_some()
+}
+
+impl LogcatInfo {
+    /// Absolute time of the line, only known when logcat printed the year (`-v year`)
+    fn wallclock(&self) -> Option<SystemTime> {
+        self.time
+            .as_ref()
+            .and_then(|time| datetime::split_datetime(time).0)
+    }
+
+    // Check if @line is the continuation of a message logged with @other
+    pub(crate) fn same_origin(&self, other: &LogcatInfo) -> bool {
+        self.pid == other.pid && self.tid == other.tid && self.tag == other.tag
+    }
+}
+
+// GStreamer only maps its levels to a subset of the logcat priorities
+fn priority_to_level(priority: char) -> DebugLevel {
+    match priority {
+        'F' | 'E' => DebugLevel::Error,
+        'W' => DebugLevel::Warning,
+        'I' => DebugLevel::Info,
+        'D' => DebugLevel::Debug,
+        _ => DebugLevel::Log,
+    }
+}
+
+/// Check if @message starts a new GStreamer entry
+pub(crate) fn is_entry_start(message: &str) -> bool {
+    TIMESTAMP_START.is_match(message.as_bytes())
+}
+
+// Parse a message logged by GStreamer's logcat handler:
+// "{ts} {thread} {file}:{line}:{function}:{object} {message}"
+fn parse_android(info: &LogcatInfo, category: &str, message: &str) -> Result<Entry, Field> {
+    let mut it = message.splitn(3, ' ');
+    let ts = parse_time(it.next().ok_or(Field::Timestamp)?)?;
+    let thread = it.next().ok_or(Field::Thread)?;
+    let mut it = it.next().ok_or(Field::Location)?.splitn(2, ' ');
+    let (file, line, function, object) = split_location(it.next().ok_or(Field::Location)?)?;
+
+    Ok(Entry {
+        ts,
+        pid: info.pid,
+        thread: thread.to_string(),
+        level: priority_to_level(info.priority),
+        category: category.to_string(),
+        file: file.to_string(),
+        line,
+        function: function.to_string(),
+        message: it.next().unwrap_or("").to_string(),
+        object: object.map(str::to_string),
+        wallclock: info.wallclock(),
+        wrapper: None,
+        continuation: Vec::new(),
+    })
+}
+
+/// Parse the GStreamer entry embedded in a logcat message. It may either have been logged
+/// by GStreamer's logcat handler, using a `GStreamer+` tag, or be a complete debug line,
+/// from redirected stderr for example.
+pub(crate) fn parse_entry(info: LogcatInfo, message: &str) -> Result<Entry, ParsingErrorKind> {
+    let entry = if info.tag.starts_with(GST_TAG_PREFIX) {
+        parse_android(&info, &info.tag[GST_TAG_PREFIX.len()..], message)
+    } else if is_entry_start(message) {
+        Entry::new(message).map(|mut entry| {
+            entry.wallclock = entry.wallclock.or_else(|| info.wallclock());
+            entry
+        })
+    } else {
+        return Err(ParsingErrorKind::Unparsed);
+    };
+
+    entry
+        .map(|mut entry| {
+            entry.wrapper = Some(Wrapper::Logcat(info));
+            entry
+        })
+        .map_err(ParsingErrorKind::InvalidField)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{parse, try_parse, InputFormat};
+
+    #[test]
+    fn threadtime() {
+        let log = "--------- beginning of main\n\
+                   06-04 10:11:12.345  1234  1250 I GStreamer+GST_INIT: 0:00:00.007773544 0x7f1c2e4c00 gst.c:510:init_pre: Initializing\n\
+                   06-04 10:11:12.346  1234  1250 D GStreamer+GST_REFCOUNTING: 0:00:00.007927372 0x7f1c2e4c00 gstobject.c:246:gst_object_ref:<pipeline0> 0x55895101d040 ref 1->2\n\
+                   06-04 10:11:12.347  1234  1251 W ActivityManager: unrelated\n";
+
+        let mut parsed = try_parse(log.as_bytes());
+        assert!(parsed.next().unwrap().is_err());
+
+        let entry = parsed.next().unwrap().unwrap();
+        assert_eq!(entry.ts.nanoseconds().unwrap(), 7773544);
+        assert_eq!(entry.pid, 1234);
+        assert_eq!(entry.thread, "0x7f1c2e4c00");
+        assert_eq!(entry.level, DebugLevel::Info);
+        assert_eq!(entry.category, "GST_INIT");
+        assert_eq!(entry.file, "gst.c");
+        assert_eq!(entry.line, 510);
+        assert_eq!(entry.function, "init_pre");
+        assert_eq!(entry.message, "Initializing");
+        assert_eq!(entry.object, None);
+        assert!(entry.wallclock.is_none());
+        assert_eq!(
+            entry.wrapper,
+            Some(Wrapper::Logcat(LogcatInfo {
+                time: Some("06-04 10:11:12.345".to_string()),
+                pid: 1234,
+                tid: Some(1250),
+                priority: 'I',
+                tag: "GStreamer+GST_INIT".to_string(),
+            }))
+        );
+
+        let entry = parsed.next().unwrap().unwrap();
+        assert_eq!(entry.level, DebugLevel::Debug);
+        assert_eq!(entry.category, "GST_REFCOUNTING");
+        assert_eq!(entry.object, Some("pipeline0".to_string()));
+        assert_eq!(entry.message, "0x55895101d040 ref 1->2");
+
+        let err = parsed.next().unwrap().unwrap_err();
+        assert_eq!(err.line_number, 4);
+        assert!(parsed.next().is_none());
+    }
+
+    #[test]
+    fn brief() {
+        let log = "I/GStreamer+GST_INIT( 1234): 0:00:00.007773544 0x7f1c2e4c00 gst.c:510:init_pre: Initializing\n\
+                   I/GStreamer+GST_INIT( 1234): continued\n\
+                   I/gst-launch( 1234): 0:00:00.008067915  1234 0x7f1c2e4c00 WARN                 default gst.c:1:main: redirected\n";
+
+        let mut parsed = parse(log.as_bytes());
+
+        let entry = parsed.next().unwrap();
+        assert_eq!(entry.category, "GST_INIT");
+        assert_eq!(entry.message, "Initializing\ncontinued");
+
+        let entry = parsed.next().unwrap();
+        assert_eq!(entry.level, DebugLevel::Warning);
+        assert_eq!(entry.category, "default");
+        assert_eq!(entry.message, "redirected");
+        match entry.wrapper {
+            Some(Wrapper::Logcat(info)) => {
+                assert_eq!(info.tag, "gst-launch");
+                assert_eq!(info.tid, None);
+                assert_eq!(info.time, None);
+            }
+            _ => panic!("missing logcat info"),
+        }
+
+        assert!(parsed.next().is_none());
+    }
+
+    #[test]
+    fn long() {
+        let log = "[ 2019-06-04 10:11:12.345  1234: 1250 I/GStreamer+GST_INIT ]\n\
+                   0:00:00.007773544 0x7f1c2e4c00 gst.c:510:init_pre: Initializing\n\
+                   \n\
+                   [ 2019-06-04 10:11:12.346  1234: 1250 I/GStreamer+GST_CAPS ]\n\
+                   0:00:00.007927372 0x7f1c2e4c00 gstcaps.c:1:dump: caps:\n\
+                   video/x-raw\n\
+                   \n";
+
+        let mut parsed = try_parse(log.as_bytes()).format(InputFormat::Logcat);
+
+        let entry = parsed.next().unwrap().unwrap();
+        assert_eq!(entry.category, "GST_INIT");
+        assert_eq!(entry.message, "Initializing");
+        assert!(entry.wallclock.is_some());
+
+        let entry = parsed.next().unwrap().unwrap();
+        assert_eq!(entry.category, "GST_CAPS");
+        assert_eq!(entry.message, "caps:\nvideo/x-raw");
+
+        assert!(parsed.next().is_none());
+    }
+}
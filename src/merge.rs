@@ -0,0 +1,361 @@
+use std::cmp::Reverse;
+use std::collections::{BinaryHeap, VecDeque};
+use std::convert::TryFrom;
+use std::error;
+use std::fmt;
+use std::time::UNIX_EPOCH;
+
+use crate::{ClockTime, Entry, Filter};
+
+/// How the running times of the merged logs are aligned
+#[derive(Debug, Clone, PartialEq)]
+pub enum Alignment {
+    /// Use the running times as they are, each process usually starting at 0
+    RunningTime,
+    /// Use the wall-clock time of the entries, the logs must contain absolute timestamps
+    Wallclock,
+    /// Consider that the first entry matching the filter happened at the same time in all the
+    /// logs, such as a buffer being sent by one process and received by the other
+    Anchor(Filter),
+}
+
+/// Error returned when the logs can't be aligned
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum MergeError {
+    /// The input with this index does not contain wall-clock timestamps
+    NoWallclock(usize),
+    /// The input with this index does not contain the anchor entry
+    AnchorNotFound(usize),
+    /// An offset has been set for this index but there is no such input
+    NoInput(usize),
+}
+
+impl fmt::Display for MergeError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            MergeError::NoWallclock(input) => {
+                write!(f, "input {} has no wall-clock timestamp", input)
+            }
+            MergeError::AnchorNotFound(input) => write!(f, "anchor not found in input {}", input),
+            MergeError::NoInput(input) => write!(f, "no input with index {}", input),
+        }
+    }
+}
+
+impl error::Error for MergeError {}
+
+/// An entry of a merged log
+#[derive(Debug, Clone, PartialEq)]
+pub struct MergedEntry {
+    /// Index of the input the entry comes from
+    pub source: usize,
+    /// The entry, with its running time shifted to the common timeline
+    pub entry: Entry,
+}
+
+/// Merge several logs, captured by different processes, onto one timeline
+pub struct Merger<I: Iterator<Item = Entry>> {
+    inputs: Vec<I>,
+    alignment: Alignment,
+    // Index of the input and its offset
+    offsets: Vec<(usize, i64)>,
+}
+
+impl<I: Iterator<Item = Entry>> Merger<I> {
+    pub fn new(inputs: Vec<I>) -> Merger<I> {
+        Merger {
+            inputs,
+            alignment: Alignment::RunningTime,
+            offsets: Vec::new(),
+        }
+    }
+
+    /// Set how the logs are aligned, using their running time by default
+    pub fn alignment(mut self, alignment: Alignment) -> Self {
+        self.alignment = alignment;
+        self
+    }
+
+    /// Shift the entries of the input at @index by @offset nanoseconds, in addition to the
+    /// alignment, to compensate for a known delay
+    pub fn offset(mut self, index: usize, offset: i64) -> Self {
+        self.offsets.push((index, offset));
+        self
+    }
+
+    /// Align the logs and return their entries ordered by running time. The entries of each
+    /// input keep their original order.
+    ///
+    /// The entries preceding the wall-clock timestamp or the anchor used to align each input
+    /// are kept in memory until it has been found.
+    pub fn merge(self) -> Result<Merge<I>, MergeError> {
+        let mut offsets = vec![0; self.inputs.len()];
+        for (index, offset) in self.offsets {
+            *offsets.get_mut(index).ok_or(MergeError::NoInput(index))? = offset;
+        }
+
+        let mut inputs: Vec<Input<I>> = self
+            .inputs
+            .into_iter()
+            .map(|entries| Input {
+                entries,
+                pending: VecDeque::new(),
+                offset: 0,
+            })
+            .collect();
+
+        for (index, input) in inputs.iter_mut().enumerate() {
+            let offset = match &self.alignment {
+                Alignment::RunningTime => 0,
+                Alignment::Wallclock => {
+                    let time = input.find(|entry| {
+                        let wallclock = entry.wallclock?.duration_since(UNIX_EPOCH).ok()?;
+                        let wallclock = i64::try_from(wallclock.as_nanos()).ok()?;
+                        ts(entry).map(|ts| (wallclock, ts))
+                    });
+                    let (wallclock, ts) = time.ok_or(MergeError::NoWallclock(index))?;
+                    wallclock - ts
+                }
+                Alignment::Anchor(filter) => {
+                    let anchor = input.find(|entry| {
+                        if filter.matches(entry) {
+                            ts(entry)
+                        } else {
+                            None
+                        }
+                    });
+                    -anchor.ok_or(MergeError::AnchorNotFound(index))?
+                }
+            };
+            input.offset = offset.saturating_add(offsets[index]);
+        }
+
+        // Shift all the inputs so the earliest one keeps its running times
+        let min_offset = inputs.iter().map(|input| input.offset).min().unwrap_or(0);
+        for input in inputs.iter_mut() {
+            input.offset = input.offset.saturating_sub(min_offset);
+        }
+
+        let mut merge = Merge {
+            inputs,
+            heads: BinaryHeap::new(),
+        };
+        for index in 0..merge.inputs.len() {
+            merge.push_next(index);
+        }
+
+        Ok(merge)
+    }
+}
+
+// The running time of @entry, if known and small enough to be shifted
+fn ts(entry: &Entry) -> Option<i64> {
+    entry.ts.nanoseconds().and_then(|ts| i64::try_from(ts).ok())
+}
+
+struct Input<I: Iterator<Item = Entry>> {
+    entries: I,
+    // Entries read while aligning the input
+    pending: VecDeque<Entry>,
+    offset: i64,
+}
+
+impl<I: Iterator<Item = Entry>> Input<I> {
+    // Read entries until @f returns a value, keeping them for later
+    fn find<T, F: Fn(&Entry) -> Option<T>>(&mut self, f: F) -> Option<T> {
+        for entry in self.entries.by_ref() {
+            let found = f(&entry);
+            self.pending.push_back(entry);
+            if found.is_some() {
+                return found;
+            }
+        }
+        None
+    }
+
+    fn next(&mut self) -> Option<Entry> {
+        self.pending.pop_front().or_else(|| self.entries.next())
+    }
+}
+
+/// Iterator over the entries of merged logs, see `Merger`
+pub struct Merge<I: Iterator<Item = Entry>> {
+    inputs: Vec<Input<I>>,
+    // Next entry of each input, ordered by shifted running time, then by input
+    heads: BinaryHeap<Reverse<(ClockTime, usize, HeadEntry)>>,
+}
+
+// Wrapper excluding the entry from the ordering of the heap
+struct HeadEntry(Entry);
+
+impl PartialEq for HeadEntry {
+    fn eq(&self, _other: &HeadEntry) -> bool {
+        true
+    }
+}
+
+impl Eq for HeadEntry {}
+
+impl PartialOrd for HeadEntry {
+    fn partial_cmp(&self, other: &HeadEntry) -> Option<std::cmp::Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+impl Ord for HeadEntry {
+    fn cmp(&self, _other: &HeadEntry) -> std::cmp::Ordering {
+        std::cmp::Ordering::Equal
+    }
+}
+
+impl<I: Iterator<Item = Entry>> Merge<I> {
+    fn push_next(&mut self, index: usize) {
+        let input = &mut self.inputs[index];
+        if let Some(mut entry) = input.next() {
+            entry.ts = ts(&entry)
+                .and_then(|ts| ts.checked_add(input.offset))
+                .and_then(|ts| u64::try_from(ts).ok())
+                .into();
+
+            self.heads
+                .push(Reverse((entry.ts, index, HeadEntry(entry))));
+        }
+    }
+}
+
+impl<I: Iterator<Item = Entry>> Iterator for Merge<I> {
+    type Item = MergedEntry;
+
+    fn next(&mut self) -> Option<MergedEntry> {
+        let Reverse((_, source, HeadEntry(entry))) = self.heads.pop()?;
+        self.push_next(source);
+
+        Some(MergedEntry { source, entry })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::parse;
+
+    const SERVER: &str = "0:00:01.000000000  100 0x1 INFO default a.c:1:f: server start\n\
+                          0:00:02.000000000  100 0x1 INFO default a.c:2:f: send buffer 1\n\
+                          0:00:04.000000000  100 0x1 INFO default a.c:3:f: send buffer 2\n";
+    const CLIENT: &str = "0:00:00.500000000  200 0x2 INFO default b.c:1:f: client start\n\
+                          0:00:01.000000000  200 0x2 INFO default b.c:2:f: received buffer 1\n\
+                          0:00:03.000000000  200 0x2 INFO default b.c:3:f: received buffer 2\n";
+
+    fn merged(merger: Merger<impl Iterator<Item = Entry>>) -> Vec<(usize, u64, u32)> {
+        merger
+            .merge()
+            .unwrap()
+            .map(|e| (e.source, e.entry.ts.mseconds().unwrap(), e.entry.line))
+            .collect()
+    }
+
+    #[test]
+    fn running_time() {
+        let inputs = vec![parse(SERVER.as_bytes()), parse(CLIENT.as_bytes())];
+        assert_eq!(
+            merged(Merger::new(inputs)),
+            vec![
+                (1, 500, 1),
+                (0, 1000, 1),
+                (1, 1000, 2),
+                (0, 2000, 2),
+                (1, 3000, 3),
+                (0, 4000, 3)
+            ]
+        );
+
+        let inputs = vec![parse(SERVER.as_bytes()), parse(CLIENT.as_bytes())];
+        assert_eq!(
+            merged(Merger::new(inputs).offset(1, 1_000_000_000)),
+            vec![
+                (0, 1000, 1),
+                (1, 1500, 1),
+                (0, 2000, 2),
+                (1, 2000, 2),
+                (0, 4000, 3),
+                (1, 4000, 3),
+            ]
+        );
+    }
+
+    #[test]
+    fn anchor() {
+        let inputs = vec![parse(SERVER.as_bytes()), parse(CLIENT.as_bytes())];
+        let anchor = Filter::parse("message~\"* buffer 1\"").unwrap();
+        assert_eq!(
+            merged(Merger::new(inputs).alignment(Alignment::Anchor(anchor))),
+            vec![
+                (0, 1000, 1),
+                (1, 1500, 1),
+                (0, 2000, 2),
+                (1, 2000, 2),
+                (0, 4000, 3),
+                (1, 4000, 3),
+            ]
+        );
+
+        let inputs = vec![parse(SERVER.as_bytes()), parse(CLIENT.as_bytes())];
+        let anchor = Filter::parse("message==\"server start\"").unwrap();
+        assert_eq!(
+            Merger::new(inputs)
+                .alignment(Alignment::Anchor(anchor))
+                .merge()
+                .err(),
+            Some(MergeError::AnchorNotFound(1))
+        );
+    }
+
+    #[test]
+    fn wallclock() {
+        let server = "2019-06-04T10:00:01.000000000Z 0:00:01.000000000  100 0x1 INFO default a.c:1:f: a\n\
+                      2019-06-04T10:00:02.000000000Z 0:00:02.000000000  100 0x1 INFO default a.c:2:f: b\n";
+        let client = "2019-06-04T10:00:01.200000000Z 0:00:00.200000000  200 0x2 INFO default b.c:1:f: c\n\
+                      2019-06-04T10:00:01.700000000Z 0:00:00.700000000  200 0x2 INFO default b.c:2:f: d\n";
+
+        let inputs = vec![parse(server.as_bytes()), parse(client.as_bytes())];
+        assert_eq!(
+            merged(Merger::new(inputs).alignment(Alignment::Wallclock)),
+            vec![(0, 1000, 1), (1, 1200, 1), (1, 1700, 2), (0, 2000, 2)]
+        );
+
+        let inputs = vec![parse(server.as_bytes()), parse(SERVER.as_bytes())];
+        assert_eq!(
+            Merger::new(inputs)
+                .alignment(Alignment::Wallclock)
+                .merge()
+                .err(),
+            Some(MergeError::NoWallclock(1))
+        );
+    }
+
+    #[test]
+    fn unknown_ts() {
+        // Too large to be shifted
+        let log = "0:00:01.000000000  100 0x1 INFO default a.c:1:f: a\n\
+                   5124095:34:33.709551615  100 0x1 INFO default a.c:2:f: b\n";
+        let inputs = vec![parse(log.as_bytes()), parse(CLIENT.as_bytes())];
+        let entries: Vec<(usize, ClockTime, u32)> = Merger::new(inputs)
+            .offset(0, 1_000)
+            .merge()
+            .unwrap()
+            .map(|e| (e.source, e.entry.ts, e.entry.line))
+            .collect();
+        assert_eq!(entries.len(), 5);
+        assert!(entries.contains(&(0, ClockTime::from_nseconds(1_000_001_000), 1)));
+        assert!(entries.contains(&(0, ClockTime::none(), 2)));
+    }
+
+    #[test]
+    fn no_input() {
+        let inputs = vec![parse(SERVER.as_bytes()), parse(CLIENT.as_bytes())];
+        assert_eq!(
+            Merger::new(inputs).offset(2, 1_000).merge().err(),
+            Some(MergeError::NoInput(2))
+        );
+    }
+}
@@ -0,0 +1,469 @@
+use std::fmt;
+use std::str::FromStr;
+
+use regex::Regex;
+
+use crate::LogObject;
+
+/// State of an element
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub enum State {
+    VoidPending,
+    Null,
+    Ready,
+    Paused,
+    Playing,
+}
+
+impl FromStr for State {
+    type Err = ();
+
+    fn from_str(s: &str) -> Result<State, ()> {
+        match s {
+            "VOID_PENDING" => Ok(State::VoidPending),
+            "NULL" => Ok(State::Null),
+            "READY" => Ok(State::Ready),
+            "PAUSED" => Ok(State::Paused),
+            "PLAYING" => Ok(State::Playing),
+            _ => Err(()),
+        }
+    }
+}
+
+impl fmt::Display for State {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let s = match self {
+            State::VoidPending => "VOID_PENDING",
+            State::Null => "NULL",
+            State::Ready => "READY",
+            State::Paused => "PAUSED",
+            State::Playing => "PLAYING",
+        };
+        f.write_str(s)
+    }
+}
+
+/// Result of a state change
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum StateChangeReturn {
+    Failure,
+    Success,
+    Async,
+    NoPreroll,
+}
+
+impl FromStr for StateChangeReturn {
+    type Err = ();
+
+    fn from_str(s: &str) -> Result<StateChangeReturn, ()> {
+        match s {
+            "FAILURE" => Ok(StateChangeReturn::Failure),
+            "SUCCESS" => Ok(StateChangeReturn::Success),
+            "ASYNC" => Ok(StateChangeReturn::Async),
+            "NO_PREROLL" => Ok(StateChangeReturn::NoPreroll),
+            _ => Err(()),
+        }
+    }
+}
+
+impl fmt::Display for StateChangeReturn {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let s = match self {
+            StateChangeReturn::Failure => "FAILURE",
+            StateChangeReturn::Success => "SUCCESS",
+            StateChangeReturn::Async => "ASYNC",
+            StateChangeReturn::NoPreroll => "NO_PREROLL",
+        };
+        f.write_str(s)
+    }
+}
+
+/// Whether a refcount message is about taking or releasing a reference
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum RefOperation {
+    Ref,
+    Unref,
+}
+
+/// The shape of a message, as recognized from the messages logged by GStreamer core
+#[derive(Debug, Clone, PartialEq)]
+pub enum MessageKind<'a> {
+    /// `0x55895101d040 ref 1->2`
+    Refcount {
+        address: u64,
+        operation: RefOperation,
+        from: u32,
+        to: u32,
+    },
+    /// `set_state to PLAYING`
+    SetState(State),
+    /// `notifying about state-changed READY to PAUSED (PLAYING pending)`
+    StateChanged {
+        old: State,
+        new: State,
+        /// `None` if no state change is pending
+        pending: Option<State>,
+    },
+    /// `completed state change to PLAYING`
+    StateCompleted(State),
+    /// `element changed state SUCCESS`, `element will change state ASYNC`,
+    /// `have FAILURE change_state return` or `returned ASYNC`
+    StateChangeReturn(StateChangeReturn),
+    /// An event dump: `stream-start event from 'src' at time 0:00:00.000000000: ...`
+    Event {
+        /// Text preceding the dump
+        prefix: &'a str,
+        name: &'a str,
+        /// The object which sent the event
+        source: &'a str,
+        time: &'a str,
+        structure: &'a str,
+    },
+    /// A query dump: `caps query: 0x7f1c2e4c00, GstQueryCaps, ...`
+    Query {
+        /// Text preceding the dump
+        prefix: &'a str,
+        name: &'a str,
+        address: u64,
+        structure: &'a str,
+    },
+    /// `linked videotestsrc0:src and fakesink0:sink, successful`,
+    /// or `link between ... failed: ...`
+    PadLink {
+        src: LogObject<'a>,
+        sink: LogObject<'a>,
+        /// The reason of the failure, if the link failed
+        error: Option<&'a str>,
+    },
+    /// Text followed by caps: `setting caps video/x-raw, format=(string)I420`
+    Caps { prefix: &'a str, caps: &'a str },
+    /// Text containing `key=value` fields, such as a serialized `GstStructure`
+    Fields {
+        /// The message without its fields
+        text: &'a str,
+        fields: Vec<(&'a str, &'a str)>,
+    },
+    /// Any other message
+    Text(&'a str),
+}
+
+lazy_static! {
+    static ref REFCOUNT: Regex =
+        Regex::new(r"^0x([0-9a-fA-F]+) (ref|unref) (\d+)->(\d+)$").unwrap();
+    static ref SET_STATE: Regex = Regex::new(r"^set_state to ([A-Z_]+)$").unwrap();
+    static ref STATE_CHANGED: Regex = Regex::new(
+        r"^notifying about state-changed ([A-Z_]+) to ([A-Z_]+) \(([A-Z_]+) pending\)$"
+    )
+    .unwrap();
+    static ref STATE_COMPLETED: Regex =
+        Regex::new(r"^completed state change to ([A-Z_]+)$").unwrap();
+    static ref STATE_CHANGE_RETURN: Regex = Regex::new(
+        r"^(?:element (?:will change|changed) state ([A-Z_]+)|have ([A-Z_]+) change_state return|returned ([A-Z_]+))$"
+    )
+    .unwrap();
+    static ref EVENT: Regex =
+        Regex::new(r"(?s)^(.*?)(\S+) event from '([^']*)' at time (\S+): (.*)$").unwrap();
+    static ref QUERY: Regex =
+        Regex::new(r"(?s)^(.*?)(\S+) query: 0x([0-9a-fA-F]+), (.*)$").unwrap();
+    static ref PAD_LINKED: Regex = Regex::new(r"^linked (\S+) and (\S+), successful$").unwrap();
+    static ref PAD_LINK_FAILED: Regex =
+        Regex::new(r"^link between (\S+) and (\S+) failed: (.*)$").unwrap();
+    // Media type starting a caps description, only the top-level types used by GStreamer are
+    // accepted so paths such as `gst/gst.c` are not mistaken for caps
+    static ref CAPS: Regex = Regex::new(
+        r"(?s)(?:^|\s)((?:application|audio|closedcaption|image|model|multipart|subpicture|text|video)/[a-zA-Z0-9.+_-]+(?:\(.*?\))?(?:[,;].*)?)$"
+    )
+    .unwrap();
+    static ref FIELD: Regex = Regex::new(r#"([a-zA-Z][a-zA-Z0-9_-]*)=("[^"]*"|\([^)]*\)"[^"]*"|[^\s,;]+)"#).unwrap();
+}
+
+fn parse_address(address: &str) -> u64 {
+    u64::from_str_radix(address, 16).unwrap_or(0)
+}
+
+impl<'a> MessageKind<'a> {
+    /// Classify @message
+    pub fn new(message: &'a str) -> MessageKind<'a> {
+        if let Some(caps) = REFCOUNT.captures(message) {
+            let operation = if &caps[2] == "ref" {
+                RefOperation::Ref
+            } else {
+                RefOperation::Unref
+            };
+            if let (Ok(from), Ok(to)) = (caps[3].parse(), caps[4].parse()) {
+                return MessageKind::Refcount {
+                    address: parse_address(&caps[1]),
+                    operation,
+                    from,
+                    to,
+                };
+            }
+        }
+
+        if let Some(caps) = SET_STATE.captures(message) {
+            if let Ok(state) = caps[1].parse() {
+                return MessageKind::SetState(state);
+            }
+        }
+
+        if let Some(caps) = STATE_CHANGED.captures(message) {
+            if let (Ok(old), Ok(new), Ok(pending)) =
+                (caps[1].parse(), caps[2].parse(), caps[3].parse())
+            {
+                let pending = if pending == State::VoidPending {
+                    None
+                } else {
+                    Some(pending)
+                };
+                return MessageKind::StateChanged { old, new, pending };
+            }
+        }
+
+        if let Some(caps) = STATE_COMPLETED.captures(message) {
+            if let Ok(state) = caps[1].parse() {
+                return MessageKind::StateCompleted(state);
+            }
+        }
+
+        if let Some(caps) = STATE_CHANGE_RETURN.captures(message) {
+            let ret = caps.iter().skip(1).flatten().next().unwrap();
+            if let Ok(ret) = ret.as_str().parse() {
+                return MessageKind::StateChangeReturn(ret);
+            }
+        }
+
+        if let Some(caps) = EVENT.captures(message) {
+            return MessageKind::Event {
+                prefix: caps.get(1).unwrap().as_str(),
+                name: caps.get(2).unwrap().as_str(),
+                source: caps.get(3).unwrap().as_str(),
+                time: caps.get(4).unwrap().as_str(),
+                structure: caps.get(5).unwrap().as_str(),
+            };
+        }
+
+        if let Some(caps) = QUERY.captures(message) {
+            return MessageKind::Query {
+                prefix: caps.get(1).unwrap().as_str(),
+                name: caps.get(2).unwrap().as_str(),
+                address: parse_address(&caps[3]),
+                structure: caps.get(4).unwrap().as_str(),
+            };
+        }
+
+        if let Some(caps) = PAD_LINKED.captures(message) {
+            return MessageKind::PadLink {
+                src: LogObject::new(caps.get(1).unwrap().as_str()),
+                sink: LogObject::new(caps.get(2).unwrap().as_str()),
+                error: None,
+            };
+        }
+
+        if let Some(caps) = PAD_LINK_FAILED.captures(message) {
+            return MessageKind::PadLink {
+                src: LogObject::new(caps.get(1).unwrap().as_str()),
+                sink: LogObject::new(caps.get(2).unwrap().as_str()),
+                error: Some(caps.get(3).unwrap().as_str()),
+            };
+        }
+
+        if let Some(caps) = CAPS.captures(message) {
+            let m = caps.get(1).unwrap();
+            return MessageKind::Caps {
+                prefix: message[..m.start()].trim_end(),
+                caps: m.as_str(),
+            };
+        }
+
+        let fields: Vec<(&str, &str)> = FIELD
+            .captures_iter(message)
+            .map(|caps| (caps.get(1).unwrap().as_str(), caps.get(2).unwrap().as_str()))
+            .collect();
+        if !fields.is_empty() {
+            let start = FIELD.find(message).unwrap().start();
+            return MessageKind::Fields {
+                text: message[..start].trim_end_matches(|c: char| c == ',' || c.is_whitespace()),
+                fields,
+            };
+        }
+
+        MessageKind::Text(message)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn refcount() {
+        assert_eq!(
+            MessageKind::new("0x55895101d040 ref 1->2"),
+            MessageKind::Refcount {
+                address: 0x5589_5101_d040,
+                operation: RefOperation::Ref,
+                from: 1,
+                to: 2
+            }
+        );
+        assert_eq!(
+            MessageKind::new("0x55895101d040 unref 2->1"),
+            MessageKind::Refcount {
+                address: 0x5589_5101_d040,
+                operation: RefOperation::Unref,
+                from: 2,
+                to: 1
+            }
+        );
+    }
+
+    #[test]
+    fn states() {
+        assert_eq!(
+            MessageKind::new("set_state to PLAYING"),
+            MessageKind::SetState(State::Playing)
+        );
+        assert_eq!(
+            MessageKind::new("notifying about state-changed READY to PAUSED (PLAYING pending)"),
+            MessageKind::StateChanged {
+                old: State::Ready,
+                new: State::Paused,
+                pending: Some(State::Playing)
+            }
+        );
+        assert_eq!(
+            MessageKind::new(
+                "notifying about state-changed PAUSED to PLAYING (VOID_PENDING pending)"
+            ),
+            MessageKind::StateChanged {
+                old: State::Paused,
+                new: State::Playing,
+                pending: None
+            }
+        );
+        assert_eq!(
+            MessageKind::new("completed state change to PAUSED"),
+            MessageKind::StateCompleted(State::Paused)
+        );
+        assert_eq!(
+            MessageKind::new("element will change state ASYNC"),
+            MessageKind::StateChangeReturn(StateChangeReturn::Async)
+        );
+        assert_eq!(
+            MessageKind::new("element changed state NO_PREROLL"),
+            MessageKind::StateChangeReturn(StateChangeReturn::NoPreroll)
+        );
+        assert_eq!(
+            MessageKind::new("have FAILURE change_state return"),
+            MessageKind::StateChangeReturn(StateChangeReturn::Failure)
+        );
+        assert_eq!(
+            MessageKind::new("returned SUCCESS"),
+            MessageKind::StateChangeReturn(StateChangeReturn::Success)
+        );
+        assert_eq!(
+            MessageKind::new("returned 42"),
+            MessageKind::Text("returned 42")
+        );
+        assert_eq!(
+            MessageKind::new("set_state to SLEEPING"),
+            MessageKind::Text("set_state to SLEEPING")
+        );
+    }
+
+    #[test]
+    fn event_query() {
+        assert_eq!(
+            MessageKind::new("sending event segment event from 'src' at time 99:99:99.999999999: GstEventSegment, segment=(GstSegment)\"GstSegment, flags=(GstSegmentFlags)GST_SEGMENT_FLAG_NONE;\";"),
+            MessageKind::Event {
+                prefix: "sending event ",
+                name: "segment",
+                source: "src",
+                time: "99:99:99.999999999",
+                structure: "GstEventSegment, segment=(GstSegment)\"GstSegment, flags=(GstSegmentFlags)GST_SEGMENT_FLAG_NONE;\";",
+            }
+        );
+        assert_eq!(
+            MessageKind::new("caps query: 0x7f1c2e4c00, GstQueryCaps, filter=(GstCaps)\"NULL\", caps=(GstCaps)\"NULL\";"),
+            MessageKind::Query {
+                prefix: "",
+                name: "caps",
+                address: 0x7f_1c2e_4c00,
+                structure: "GstQueryCaps, filter=(GstCaps)\"NULL\", caps=(GstCaps)\"NULL\";",
+            }
+        );
+    }
+
+    #[test]
+    fn pad_link() {
+        assert_eq!(
+            MessageKind::new("linked videotestsrc0:src and fakesink0:sink, successful"),
+            MessageKind::PadLink {
+                src: LogObject::Pad {
+                    element: Some("videotestsrc0"),
+                    pad: "src"
+                },
+                sink: LogObject::Pad {
+                    element: Some("fakesink0"),
+                    pad: "sink"
+                },
+                error: None,
+            }
+        );
+        match MessageKind::new("link between src:src and sink:sink failed: no common format") {
+            MessageKind::PadLink { error, .. } => assert_eq!(error, Some("no common format")),
+            kind => panic!("wrong kind: {:?}", kind),
+        }
+    }
+
+    #[test]
+    fn caps() {
+        assert_eq!(
+            MessageKind::new("setting caps video/x-raw, format=(string)I420, width=(int)320"),
+            MessageKind::Caps {
+                prefix: "setting caps",
+                caps: "video/x-raw, format=(string)I420, width=(int)320"
+            }
+        );
+        assert_eq!(
+            MessageKind::new("video/x-raw(memory:GLMemory)"),
+            MessageKind::Caps {
+                prefix: "",
+                caps: "video/x-raw(memory:GLMemory)"
+            }
+        );
+    }
+
+    #[test]
+    fn not_caps() {
+        for message in &[
+            "opening gst/foo.c",
+            "and/or",
+            "loading plugin from lib/gstreamer-1.0/libgstcoreelements.so",
+        ] {
+            assert_eq!(MessageKind::new(message), MessageKind::Text(message));
+        }
+    }
+
+    #[test]
+    fn fields() {
+        assert_eq!(
+            MessageKind::new("pushing buffer, pts=0:00:00.033333333 size=4096"),
+            MessageKind::Fields {
+                text: "pushing buffer",
+                fields: vec![("pts", "0:00:00.033333333"), ("size", "4096")],
+            }
+        );
+        assert_eq!(
+            MessageKind::new("EmptyThisBuffer, TimeStamp=(guint64)42, name=(string)\"a b\";"),
+            MessageKind::Fields {
+                text: "EmptyThisBuffer",
+                fields: vec![("TimeStamp", "(guint64)42"), ("name", "(string)\"a b\"")],
+            }
+        );
+        assert_eq!(
+            MessageKind::new("Initializing GStreamer Core Library version 1.10.4"),
+            MessageKind::Text("Initializing GStreamer Core Library version 1.10.4")
+        );
+    }
+}
@@ -0,0 +1,175 @@
+use std::fmt;
+
+/// The object an entry has been logged for, as formatted by GStreamer's debug system
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum LogObject<'a> {
+    /// A named object, usually an element but it may also be a bus, a clock, an allocator, etc.
+    Element(&'a str),
+    /// A pad, described as `element:pad`. The parent of a proxy pad is its ghost pad,
+    /// `element` is `None` for pads without parent.
+    Pad {
+        element: Option<&'a str>,
+        pad: &'a str,
+    },
+    /// An object without name, such as a `GstBuffer`, described as `Type@0xADDR`
+    Anonymous { type_name: &'a str, address: u64 },
+    /// Anything not matching the other variants
+    Other(&'a str),
+}
+
+// Characters GStreamer accepts in object names
+fn is_name(s: &str) -> bool {
+    !s.is_empty()
+        && s.chars()
+            .all(|c| !c.is_whitespace() && !c.is_control() && c != ':' && c != '@')
+}
+
+impl<'a> LogObject<'a> {
+    /// Parse the object field, without the surrounding `<` and `>`
+    pub fn new(object: &'a str) -> LogObject<'a> {
+        if let Some(pos) = object.find('@') {
+            let (type_name, address) = (&object[..pos], &object[pos + 1..]);
+            let address = address
+                .strip_prefix("0x")
+                .and_then(|address| u64::from_str_radix(address, 16).ok());
+
+            return match address {
+                Some(address) if is_name(type_name) => LogObject::Anonymous { type_name, address },
+                _ => LogObject::Other(object),
+            };
+        }
+
+        let mut split = object.split(':');
+        match (split.next(), split.next(), split.next()) {
+            (Some(element), None, None) if is_name(element) => LogObject::Element(element),
+            (Some(element), Some(pad), None) if is_name(pad) => {
+                if element == "''" {
+                    LogObject::Pad { element: None, pad }
+                } else if is_name(element) {
+                    LogObject::Pad {
+                        element: Some(element),
+                        pad,
+                    }
+                } else {
+                    LogObject::Other(object)
+                }
+            }
+            _ => LogObject::Other(object),
+        }
+    }
+
+    /// The name of the element, or of the parent element of the pad
+    pub fn element(&self) -> Option<&'a str> {
+        match *self {
+            LogObject::Element(element) => Some(element),
+            LogObject::Pad { element, .. } => element,
+            _ => None,
+        }
+    }
+
+    /// The name of the pad
+    pub fn pad(&self) -> Option<&'a str> {
+        match *self {
+            LogObject::Pad { pad, .. } => Some(pad),
+            _ => None,
+        }
+    }
+
+    /// The name of the OMX component wrapped by an element, as used by the OMX tools:
+    /// the last four characters of the element name, such as `dec0` for `omxh264dec-omxh264dec0`
+    pub fn omx_component(&self) -> Option<&'a str> {
+        let element = self.element()?;
+        let (i, _) = element.char_indices().rev().nth(3)?;
+        Some(&element[i..])
+    }
+
+    /// The address of an anonymous object
+    pub fn address(&self) -> Option<u64> {
+        match *self {
+            LogObject::Anonymous { address, .. } => Some(address),
+            _ => None,
+        }
+    }
+}
+
+impl<'a> fmt::Display for LogObject<'a> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            LogObject::Element(element) => f.write_str(element),
+            LogObject::Pad { element, pad } => write!(f, "{}:{}", element.unwrap_or("''"), pad),
+            LogObject::Anonymous { type_name, address } => {
+                write!(f, "{}@{:#x}", type_name, address)
+            }
+            LogObject::Other(object) => f.write_str(object),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parse() {
+        let object = LogObject::new("videotestsrc0");
+        assert_eq!(object, LogObject::Element("videotestsrc0"));
+        assert_eq!(object.element(), Some("videotestsrc0"));
+        assert_eq!(object.pad(), None);
+
+        let object = LogObject::new("videotestsrc0:src");
+        assert_eq!(object.element(), Some("videotestsrc0"));
+        assert_eq!(object.pad(), Some("src"));
+
+        // Proxy pad of a ghost pad
+        let object = LogObject::new("sink:proxypad0");
+        assert_eq!(object.element(), Some("sink"));
+        assert_eq!(object.pad(), Some("proxypad0"));
+
+        let object = LogObject::new("'':sink");
+        assert_eq!(
+            object,
+            LogObject::Pad {
+                element: None,
+                pad: "sink"
+            }
+        );
+
+        let object = LogObject::new("GstBuffer@0x7f1c2e4c00");
+        assert_eq!(
+            object,
+            LogObject::Anonymous {
+                type_name: "GstBuffer",
+                address: 0x7f_1c2e_4c00
+            }
+        );
+        assert_eq!(object.address(), Some(0x7f_1c2e_4c00));
+        assert_eq!(object.element(), None);
+
+        let object = LogObject::new("omxh264dec-omxh264dec0");
+        assert_eq!(object.omx_component(), Some("dec0"));
+        let object = LogObject::new("omxh264dec-omxh264dec0:src");
+        assert_eq!(object.omx_component(), Some("dec0"));
+        assert_eq!(LogObject::new("abc").omx_component(), None);
+        assert_eq!(LogObject::new("GstBuffer@0x1").omx_component(), None);
+
+        assert_eq!(LogObject::new("a:b:c"), LogObject::Other("a:b:c"));
+        assert_eq!(
+            LogObject::new("GstBuffer@nope"),
+            LogObject::Other("GstBuffer@nope")
+        );
+        assert_eq!(LogObject::new("two words"), LogObject::Other("two words"));
+    }
+
+    #[test]
+    fn display() {
+        for object in &[
+            "videotestsrc0",
+            "videotestsrc0:src",
+            "'':sink",
+            "GstBuffer@0x7f1c2e4c00",
+            "a:b:c",
+        ] {
+            assert_eq!(LogObject::new(object).to_string(), *object);
+        }
+    }
+}
@@ -0,0 +1,207 @@
+use std::collections::BTreeMap;
+use std::fs::File;
+use std::io;
+use std::io::BufReader;
+use std::path::Path;
+use std::sync::mpsc;
+use std::sync::Arc;
+use std::thread;
+
+use memmap::Mmap;
+use rayon::prelude::*;
+
+use crate::{Entry, ParserIterator, TIMESTAMP_START};
+
+const DEFAULT_CHUNK_SIZE: usize = 16 * 1024 * 1024;
+
+/// Parser splitting a memory-mapped log file into chunks which are parsed in parallel.
+/// Produces the same entries as `parse()`.
+pub struct ParallelParser {
+    // None if the file is empty as it cannot be mapped
+    mmap: Option<Arc<Mmap>>,
+    chunk_size: usize,
+}
+
+impl ParallelParser {
+    fn new(mmap: Option<Mmap>) -> Self {
+        Self {
+            mmap: mmap.map(Arc::new),
+            chunk_size: DEFAULT_CHUNK_SIZE,
+        }
+    }
+
+    /// Set the approximate size, in bytes, of the chunks parsed by each job
+    pub fn chunk_size(mut self, chunk_size: usize) -> Self {
+        self.chunk_size = chunk_size.max(1);
+        self
+    }
+
+    /// Parse the whole file, returning the entries in their original order
+    pub fn entries(&self) -> impl Iterator<Item = Entry> {
+        let window = max_pending();
+
+        Ordered {
+            receiver: self.spawn(window),
+            pending: BTreeMap::new(),
+            next: 0,
+        }
+        .flatten()
+    }
+
+    /// Parse the whole file, returning the entries of each chunk as soon as it has been parsed.
+    /// Chunks are yielded in no particular order.
+    pub fn batches(&self) -> impl Iterator<Item = Vec<Entry>> {
+        self.spawn(usize::MAX)
+            .into_iter()
+            .map(|(_index, entries)| entries)
+    }
+
+    // Parse the chunks from a separate thread, sending the entries of each chunk with its index.
+    // Chunks are parsed @window at a time and the channel is bounded so only a limited number of
+    // parsed chunks are waiting to be consumed.
+    fn spawn(&self, window: usize) -> mpsc::Receiver<(usize, Vec<Entry>)> {
+        let mmap = self.mmap.clone();
+        let chunk_size = self.chunk_size;
+        let (sender, receiver) = mpsc::sync_channel(max_pending());
+
+        thread::spawn(move || {
+            let data = data(&mmap);
+            let chunks = split_chunks(data, chunk_size);
+
+            for (n, chunks) in chunks.chunks(window).enumerate() {
+                let dropped = chunks
+                    .par_iter()
+                    .enumerate()
+                    .map_with(sender.clone(), |sender, (i, chunk)| {
+                        let entries = parse_chunk(&data[chunk.0..chunk.1]);
+                        sender.send((n * window + i, entries)).is_err()
+                    })
+                    .any(|dropped| dropped);
+                // The receiver has been dropped, nothing to do then
+                if dropped {
+                    break;
+                }
+            }
+        });
+
+        receiver
+    }
+}
+
+// Maximum number of parsed chunks waiting to be consumed
+fn max_pending() -> usize {
+    rayon::current_num_threads() * 2
+}
+
+// Yield the entries of the chunks, received in any order, in their original order
+struct Ordered {
+    receiver: mpsc::Receiver<(usize, Vec<Entry>)>,
+    // Chunks received before the ones preceding them, indexed by their position in the file
+    pending: BTreeMap<usize, Vec<Entry>>,
+    next: usize,
+}
+
+impl Iterator for Ordered {
+    type Item = Vec<Entry>;
+
+    fn next(&mut self) -> Option<Vec<Entry>> {
+        loop {
+            if let Some(entries) = self.pending.remove(&self.next) {
+                self.next += 1;
+                return Some(entries);
+            }
+
+            let (index, entries) = self.receiver.recv().ok()?;
+            self.pending.insert(index, entries);
+        }
+    }
+}
+
+fn data(mmap: &Option<Arc<Mmap>>) -> &[u8] {
+    match mmap {
+        Some(mmap) => &mmap[..],
+        None => &[],
+    }
+}
+
+// Split @data into chunks of about @chunk_size bytes. Each chunk starts with an entry
+// header so multi-line messages are not split.
+fn split_chunks(data: &[u8], chunk_size: usize) -> Vec<(usize, usize)> {
+    let mut chunks = Vec::new();
+    let mut start = 0;
+
+    while start < data.len() {
+        let mut end = start + chunk_size;
+
+        loop {
+            if end >= data.len() {
+                end = data.len();
+                break;
+            }
+
+            // Move to the beginning of the next line
+            match data[end..].iter().position(|c| *c == b'\n') {
+                None => end = data.len(),
+                Some(pos) => end += pos + 1,
+            }
+
+            if end >= data.len() || TIMESTAMP_START.is_match(&data[end..]) {
+                break;
+            }
+        }
+
+        chunks.push((start, end));
+        start = end;
+    }
+
+    chunks
+}
+
+fn parse_chunk(chunk: &[u8]) -> Vec<Entry> {
+    ParserIterator::new(BufReader::new(chunk))
+        .filter_map(Result::ok)
+        .collect()
+}
+
+/// Memory-map the log file at @path so it can be parsed in parallel
+pub fn parse_file_parallel<P: AsRef<Path>>(path: P) -> io::Result<ParallelParser> {
+    let file = File::open(path)?;
+    let mmap = if file.metadata()?.len() > 0 {
+        Some(unsafe { Mmap::map(&file)? })
+    } else {
+        None
+    };
+
+    Ok(ParallelParser::new(mmap))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::parse;
+
+    #[test]
+    fn same_as_parse() {
+        for path in &["test-logs/nocolor.log", "test-logs/color.log"] {
+            let f = File::open(path).expect("Failed to open log file");
+            let expected: Vec<Entry> = parse(f).collect();
+
+            for chunk_size in &[1, 100, 1000, DEFAULT_CHUNK_SIZE] {
+                let parser = parse_file_parallel(path)
+                    .expect("Failed to open log file")
+                    .chunk_size(*chunk_size);
+
+                let entries: Vec<Entry> = parser.entries().collect();
+                assert_eq!(entries, expected);
+
+                // Parsing stops once the iterator is dropped
+                let entries: Vec<Entry> = parser.entries().take(3).collect();
+                assert_eq!(entries, expected[..3]);
+
+                let mut entries: Vec<Entry> = parser.batches().flatten().collect();
+                entries.sort_by_key(|e| e.ts);
+                assert_eq!(entries, expected);
+            }
+        }
+    }
+}
@@ -0,0 +1,242 @@
+use serde::ser::{Serialize, SerializeMap, SerializeSeq, SerializeStruct, Serializer};
+use serde::Serialize as DeriveSerialize;
+
+use crate::datetime;
+use crate::{Caps, ClockTime, DebugLevel, Entry, LogObject, Structure, Value};
+
+// Field names are part of the output format and should not be changed
+
+impl Serialize for ClockTime {
+    /// Serialized as a number of nanoseconds, or `null`
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        self.nanoseconds().serialize(serializer)
+    }
+}
+
+impl Serialize for DebugLevel {
+    /// Serialized as the name used in logs, such as `WARN`
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.serialize_str(self.as_str())
+    }
+}
+
+impl<'a> Serialize for LogObject<'a> {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        let mut map = serializer.serialize_map(None)?;
+        match *self {
+            LogObject::Element(element) => {
+                map.serialize_entry("kind", "element")?;
+                map.serialize_entry("element", element)?;
+            }
+            LogObject::Pad { element, pad } => {
+                map.serialize_entry("kind", "pad")?;
+                map.serialize_entry("element", &element)?;
+                map.serialize_entry("pad", pad)?;
+            }
+            LogObject::Anonymous { type_name, address } => {
+                map.serialize_entry("kind", "anonymous")?;
+                map.serialize_entry("type", type_name)?;
+                map.serialize_entry("address", &format!("{:#x}", address))?;
+            }
+            LogObject::Other(object) => {
+                map.serialize_entry("kind", "other")?;
+                map.serialize_entry("name", object)?;
+            }
+        }
+        map.end()
+    }
+}
+
+impl Serialize for Value {
+    /// Numbers, booleans, strings, lists and arrays are mapped to their natural
+    /// representation. Fractions, caps and values of unknown types are serialized as text.
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        match self {
+            Value::Bool(v) => serializer.serialize_bool(*v),
+            Value::Int(v) => serializer.serialize_i32(*v),
+            Value::UInt(v) => serializer.serialize_u32(*v),
+            Value::Int64(v) => serializer.serialize_i64(*v),
+            Value::UInt64(v) => serializer.serialize_u64(*v),
+            Value::Float(v) => serializer.serialize_f32(*v),
+            Value::Double(v) => serializer.serialize_f64(*v),
+            Value::String(v) => serializer.serialize_str(v),
+            Value::Fraction(num, den) => serializer.collect_str(&format_args!("{}/{}", num, den)),
+            Value::Range { min, max, step } => {
+                let mut map = serializer.serialize_map(None)?;
+                map.serialize_entry("min", min)?;
+                map.serialize_entry("max", max)?;
+                if let Some(step) = step {
+                    map.serialize_entry("step", step)?;
+                }
+                map.end()
+            }
+            Value::List(values) | Value::Array(values) => {
+                let mut seq = serializer.serialize_seq(Some(values.len()))?;
+                for v in values {
+                    seq.serialize_element(v)?;
+                }
+                seq.end()
+            }
+            Value::Structure(s) => s.serialize(serializer),
+            Value::Caps(caps) => caps.serialize(serializer),
+            Value::Other { value, .. } => serializer.serialize_str(value),
+        }
+    }
+}
+
+struct Fields<'a>(&'a Structure);
+
+impl<'a> Serialize for Fields<'a> {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.collect_map(self.0.fields())
+    }
+}
+
+impl Serialize for Structure {
+    /// Serialized as `{"name": ..., "fields": {...}}`
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        let mut s = serializer.serialize_struct("Structure", 2)?;
+        s.serialize_field("name", self.get_name())?;
+        s.serialize_field("fields", &Fields(self))?;
+        s.end()
+    }
+}
+
+impl Serialize for Caps {
+    /// Serialized using their textual form
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.collect_str(self)
+    }
+}
+
+impl Serialize for Entry {
+    /// The object is serialized both as logged and decomposed into its parts.
+    /// If the message is a serialized structure, its fields are included as well.
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        let mut s = serializer.serialize_struct("Entry", 14)?;
+        s.serialize_field("ts", &self.ts)?;
+        s.serialize_field("wallclock", &self.wallclock.map(datetime::format_datetime))?;
+        s.serialize_field("pid", &self.pid)?;
+        s.serialize_field("thread", &self.thread)?;
+        s.serialize_field("level", &self.level)?;
+        s.serialize_field("category", &self.category)?;
+        s.serialize_field("file", &self.file)?;
+        s.serialize_field("line", &self.line)?;
+        s.serialize_field("function", &self.function)?;
+        s.serialize_field("object", &self.object)?;
+        s.serialize_field("log_object", &self.log_object())?;
+        s.serialize_field("message", &self.message)?;
+        s.serialize_field("continuation", &self.continuation)?;
+        s.serialize_field("structure", &self.message_structure())?;
+        s.end()
+    }
+}
+
+/// A flat view of an entry, for formats not supporting nested values such as CSV.
+/// The parts of the object are split into their own columns.
+#[derive(Debug, DeriveSerialize)]
+pub struct FlatEntry<'a> {
+    pub ts: ClockTime,
+    pub wallclock: Option<String>,
+    pub pid: u32,
+    pub thread: &'a str,
+    pub level: DebugLevel,
+    pub category: &'a str,
+    pub file: &'a str,
+    pub line: u32,
+    pub function: &'a str,
+    pub object: Option<&'a str>,
+    pub element: Option<&'a str>,
+    pub pad: Option<&'a str>,
+    pub message: &'a str,
+}
+
+impl<'a> From<&'a Entry> for FlatEntry<'a> {
+    fn from(entry: &'a Entry) -> FlatEntry<'a> {
+        let log_object = entry.log_object();
+
+        FlatEntry {
+            ts: entry.ts,
+            wallclock: entry.wallclock.map(datetime::format_datetime),
+            pid: entry.pid,
+            thread: &entry.thread,
+            level: entry.level,
+            category: &entry.category,
+            file: &entry.file,
+            line: entry.line,
+            function: &entry.function,
+            object: entry.object.as_deref(),
+            element: log_object.and_then(|o| o.element()),
+            pad: log_object.and_then(|o| o.pad()),
+            message: &entry.message,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::parse;
+
+    fn entries(log: &str) -> Vec<Entry> {
+        parse(log.as_bytes()).collect()
+    }
+
+    #[test]
+    fn json() {
+        let entries = entries(
+            "0:00:00.007927372 23464 0x5589510ab9e0 DEBUG     GST_REFCOUNTING gstobject.c:246:gst_object_ref:<src:src> 0x55895101d040 ref 1->2\n\
+             0:00:01.000000000 23464 0x5589510ab9e0 TRACE          GST_TRACER :0:: buffer, ts=(guint64)42, size=(uint){ 1, 2 }, rate=(fraction)30/1;\n",
+        );
+
+        let json = serde_json::to_value(&entries[0]).unwrap();
+        assert_eq!(
+            json,
+            serde_json::json!({
+                "ts": 7927372,
+                "wallclock": null,
+                "pid": 23464,
+                "thread": "0x5589510ab9e0",
+                "level": "DEBUG",
+                "category": "GST_REFCOUNTING",
+                "file": "gstobject.c",
+                "line": 246,
+                "function": "gst_object_ref",
+                "object": "src:src",
+                "log_object": {"kind": "pad", "element": "src", "pad": "src"},
+                "message": "0x55895101d040 ref 1->2",
+                "continuation": [],
+                "structure": null,
+            })
+        );
+
+        let json = serde_json::to_value(&entries[1]).unwrap();
+        assert_eq!(json["level"], "TRACE");
+        assert_eq!(json["object"], serde_json::Value::Null);
+        assert_eq!(json["log_object"], serde_json::Value::Null);
+        assert_eq!(
+            json["structure"],
+            serde_json::json!({
+                "name": "buffer",
+                "fields": {"ts": 42, "size": [1, 2], "rate": "30/1"},
+            })
+        );
+    }
+
+    #[test]
+    fn csv() {
+        let entries = entries(
+            "0:00:00.007927372 23464 0x5589510ab9e0 DEBUG     GST_REFCOUNTING gstobject.c:246:gst_object_ref:<src:src> 0x55895101d040 ref, 1->2\n",
+        );
+
+        let mut writer = csv::Writer::from_writer(vec![]);
+        writer.serialize(FlatEntry::from(&entries[0])).unwrap();
+        let output = String::from_utf8(writer.into_inner().unwrap()).unwrap();
+
+        assert_eq!(
+            output,
+            "ts,wallclock,pid,thread,level,category,file,line,function,object,element,pad,message\n\
+             7927372,,23464,0x5589510ab9e0,DEBUG,GST_REFCOUNTING,gstobject.c,246,gst_object_ref,src:src,src,src,\"0x55895101d040 ref, 1->2\"\n"
+        );
+    }
+}
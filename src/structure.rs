@@ -0,0 +1,859 @@
+use std::convert::TryFrom;
+use std::error;
+use std::fmt;
+use std::str;
+
+/// A value of a `Structure` field
+#[derive(Debug, Clone, PartialEq)]
+pub enum Value {
+    Bool(bool),
+    Int(i32),
+    UInt(u32),
+    Int64(i64),
+    UInt64(u64),
+    Float(f32),
+    Double(f64),
+    String(String),
+    Fraction(i32, i32),
+    /// `[ min, max ]` or `[ min, max, step ]`
+    Range {
+        min: Box<Value>,
+        max: Box<Value>,
+        step: Option<Box<Value>>,
+    },
+    /// Unordered list of possible values: `{ a, b }`
+    List(Vec<Value>),
+    /// Ordered array: `< a, b >`
+    Array(Vec<Value>),
+    Structure(Structure),
+    Caps(Caps),
+    /// Value of a type not handled by this parser, such as enums, flags or boxed types,
+    /// kept as its serialized text
+    Other {
+        type_name: String,
+        value: String,
+        quoted: bool,
+    },
+}
+
+/// A `GstStructure`, parsed from its serialized form
+#[derive(Debug, Clone, PartialEq)]
+pub struct Structure {
+    name: String,
+    fields: Vec<(String, Value)>,
+}
+
+/// `GstCaps`, parsed from their serialized form
+#[derive(Debug, Clone, PartialEq)]
+pub enum Caps {
+    Any,
+    /// Structures with their optional caps features, such as `memory:GLMemory`.
+    /// Empty caps have no structure.
+    Structures(Vec<(Structure, Option<String>)>),
+}
+
+/// Error returned when failing to parse a serialized structure or caps
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ParseStructureError {
+    /// Byte offset of the error in the parsed text
+    pub position: usize,
+    pub reason: &'static str,
+}
+
+impl fmt::Display for ParseStructureError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{} at offset {}", self.reason, self.position)
+    }
+}
+
+impl error::Error for ParseStructureError {}
+
+// Characters which can be used in unquoted strings, as GST_ASCII_IS_STRING()
+fn is_string_char(c: u8) -> bool {
+    c.is_ascii_alphanumeric() || b"_-+/:.".contains(&c)
+}
+
+fn is_structure_type(type_name: Option<&str>) -> bool {
+    type_name == Some("structure") || type_name == Some("GstStructure")
+}
+
+fn is_caps_type(type_name: Option<&str>) -> bool {
+    type_name == Some("caps") || type_name == Some("GstCaps")
+}
+
+fn parse_integer<T: str::FromStr + TryFrom<i128>>(text: &str) -> Option<T> {
+    let (negative, digits) = match text.strip_prefix('-') {
+        Some(digits) => (true, digits),
+        None => (false, text),
+    };
+
+    if let Some(hex) = digits
+        .strip_prefix("0x")
+        .or_else(|| digits.strip_prefix("0X"))
+    {
+        // Parsed as u64 so the values above i64::MAX, such as flags, are accepted
+        let value = i128::from(u64::from_str_radix(hex, 16).ok()?);
+        T::try_from(if negative { -value } else { value }).ok()
+    } else {
+        text.parse().ok()
+    }
+}
+
+fn parse_bool(text: &str) -> Option<bool> {
+    match text.to_ascii_lowercase().as_str() {
+        "true" | "yes" | "t" | "1" => Some(true),
+        "false" | "no" | "f" | "0" => Some(false),
+        _ => None,
+    }
+}
+
+fn parse_fraction(text: &str) -> Option<Value> {
+    let mut split = text.splitn(2, '/');
+    let num = parse_integer(split.next()?)?;
+    let denom = match split.next() {
+        Some(denom) => parse_integer(denom)?,
+        None => 1,
+    };
+
+    Some(Value::Fraction(num, denom))
+}
+
+// Convert the text of a simple value to the given type, or guess its type as GStreamer does
+fn convert(type_name: Option<&str>, text: String, quoted: bool) -> Result<Value, &'static str> {
+    let value = match type_name {
+        None if quoted => Some(Value::String(text)),
+        None => {
+            let value = parse_integer(&text)
+                .map(Value::Int)
+                .or_else(|| text.parse().ok().map(Value::Double))
+                .or_else(|| parse_fraction(&text))
+                .or_else(|| parse_bool(&text).map(Value::Bool));
+            return Ok(value.unwrap_or(Value::String(text)));
+        }
+        Some("int") | Some("i") | Some("gint") => parse_integer(&text).map(Value::Int),
+        Some("uint") | Some("u") | Some("guint") => parse_integer(&text).map(Value::UInt),
+        Some("int64") | Some("gint64") | Some("long") | Some("glong") => {
+            parse_integer(&text).map(Value::Int64)
+        }
+        Some("uint64") | Some("guint64") | Some("ulong") | Some("gulong") => {
+            parse_integer(&text).map(Value::UInt64)
+        }
+        Some("float") | Some("f") | Some("gfloat") => text.parse().ok().map(Value::Float),
+        Some("double") | Some("d") | Some("gdouble") => text.parse().ok().map(Value::Double),
+        Some("boolean") | Some("bool") | Some("b") | Some("gboolean") => {
+            parse_bool(&text).map(Value::Bool)
+        }
+        Some("string") | Some("str") | Some("s") | Some("gchararray") => Some(Value::String(text)),
+        Some("fraction") | Some("GstFraction") => parse_fraction(&text),
+        t if is_structure_type(t) => Structure::parse(&text).ok().map(Value::Structure),
+        t if is_caps_type(t) => Caps::parse(&text).ok().map(Value::Caps),
+        Some(type_name) => Some(Value::Other {
+            type_name: type_name.to_string(),
+            value: text,
+            quoted,
+        }),
+    };
+
+    value.ok_or("invalid value")
+}
+
+// Maximum nesting of values, such as lists of lists, so corrupted logs can't overflow the stack
+const MAX_DEPTH: usize = 64;
+
+struct Parser<'a> {
+    s: &'a [u8],
+    pos: usize,
+    // Number of values being parsed, each one containing the next one
+    depth: usize,
+}
+
+type ParseResult<T> = Result<T, ParseStructureError>;
+
+impl<'a> Parser<'a> {
+    fn new(s: &'a str) -> Self {
+        Self {
+            s: s.as_bytes(),
+            pos: 0,
+            depth: 0,
+        }
+    }
+
+    fn error<T>(&self, reason: &'static str) -> ParseResult<T> {
+        Err(ParseStructureError {
+            position: self.pos,
+            reason,
+        })
+    }
+
+    fn peek(&self) -> Option<u8> {
+        self.s.get(self.pos).copied()
+    }
+
+    fn peek_is<F: Fn(u8) -> bool>(&self, f: F) -> bool {
+        match self.peek() {
+            Some(c) => f(c),
+            None => false,
+        }
+    }
+
+    fn skip_spaces(&mut self) {
+        while self.peek_is(|c| c.is_ascii_whitespace()) {
+            self.pos += 1;
+        }
+    }
+
+    fn expect(&mut self, c: u8, reason: &'static str) -> ParseResult<()> {
+        self.skip_spaces();
+        if self.peek() == Some(c) {
+            self.pos += 1;
+            Ok(())
+        } else {
+            self.error(reason)
+        }
+    }
+
+    fn at_end(&mut self) -> bool {
+        self.skip_spaces();
+        self.pos >= self.s.len()
+    }
+
+    fn simple_string(&mut self) -> &'a str {
+        let start = self.pos;
+        while self.peek_is(is_string_char) {
+            self.pos += 1;
+        }
+        // Only ASCII characters have been consumed
+        str::from_utf8(&self.s[start..self.pos]).unwrap()
+    }
+
+    fn quoted_string(&mut self) -> ParseResult<String> {
+        // Skip opening quote
+        self.pos += 1;
+        let mut bytes = Vec::new();
+
+        loop {
+            match self.peek() {
+                None => return self.error("unterminated string"),
+                Some(b'"') => {
+                    self.pos += 1;
+                    break;
+                }
+                Some(b'\\') => {
+                    self.pos += 1;
+                    let octal = self
+                        .s
+                        .get(self.pos..self.pos + 3)
+                        .and_then(|o| str::from_utf8(o).ok())
+                        .and_then(|o| u8::from_str_radix(o, 8).ok());
+                    match octal {
+                        Some(c) => {
+                            bytes.push(c);
+                            self.pos += 3;
+                        }
+                        None => match self.peek() {
+                            Some(c) => {
+                                bytes.push(c);
+                                self.pos += 1;
+                            }
+                            None => return self.error("unterminated string"),
+                        },
+                    }
+                }
+                Some(c) => {
+                    bytes.push(c);
+                    self.pos += 1;
+                }
+            }
+        }
+
+        Ok(String::from_utf8_lossy(&bytes).into_owned())
+    }
+
+    // Parse a structure, with its caps features if @features is true
+    fn structure(&mut self, features: bool) -> ParseResult<(Structure, Option<String>)> {
+        self.skip_spaces();
+        let name = self.simple_string();
+        if !name.starts_with(|c: char| c.is_ascii_alphabetic()) {
+            return self.error("invalid structure name");
+        }
+        let mut structure = Structure::new(name);
+
+        let mut caps_features = None;
+        if features && self.peek() == Some(b'(') {
+            let start = self.pos + 1;
+            while self.peek_is(|c| c != b')') {
+                self.pos += 1;
+            }
+            caps_features = Some(String::from_utf8_lossy(&self.s[start..self.pos]).into_owned());
+            self.expect(b')', "unterminated caps features")?;
+        }
+
+        loop {
+            self.skip_spaces();
+            match self.peek() {
+                None | Some(b']') => break,
+                Some(b';') => {
+                    self.pos += 1;
+                    break;
+                }
+                Some(b',') => {
+                    self.pos += 1;
+                    self.skip_spaces();
+                    let field = self.simple_string();
+                    if field.is_empty() {
+                        return self.error("missing field name");
+                    }
+                    self.expect(b'=', "missing '=' after field name")?;
+                    let value = self.value(None)?;
+                    structure.set(field, value);
+                }
+                Some(_) => return self.error("expected ',' or ';'"),
+            }
+        }
+
+        Ok((structure, caps_features))
+    }
+
+    fn caps(&mut self) -> ParseResult<Caps> {
+        let mut structures = Vec::new();
+        loop {
+            self.skip_spaces();
+            match self.peek() {
+                None | Some(b']') => break,
+                _ => structures.push(self.structure(true)?),
+            }
+        }
+        Ok(Caps::Structures(structures))
+    }
+
+    fn values(&mut self, type_name: Option<&str>, end: u8) -> ParseResult<Vec<Value>> {
+        // Skip opening delimiter
+        self.pos += 1;
+        let mut values = Vec::new();
+
+        self.skip_spaces();
+        if self.peek() == Some(end) {
+            self.pos += 1;
+            return Ok(values);
+        }
+
+        loop {
+            values.push(self.value(type_name)?);
+            self.skip_spaces();
+            match self.peek() {
+                Some(b',') => self.pos += 1,
+                Some(c) if c == end => {
+                    self.pos += 1;
+                    return Ok(values);
+                }
+                _ => return self.error("expected ',' or end of list"),
+            }
+        }
+    }
+
+    fn value(&mut self, type_name: Option<&str>) -> ParseResult<Value> {
+        if self.depth == MAX_DEPTH {
+            return self.error("too many nested values");
+        }
+
+        self.depth += 1;
+        let value = self.nested_value(type_name);
+        self.depth -= 1;
+        value
+    }
+
+    fn nested_value(&mut self, type_name: Option<&str>) -> ParseResult<Value> {
+        self.skip_spaces();
+
+        let mut type_name = type_name;
+        if self.peek() == Some(b'(') {
+            self.pos += 1;
+            self.skip_spaces();
+            type_name = Some(self.simple_string());
+            self.expect(b')', "unterminated type name")?;
+            self.skip_spaces();
+        }
+
+        match self.peek() {
+            Some(b'[') if is_structure_type(type_name) => {
+                self.pos += 1;
+                let (structure, _) = self.structure(false)?;
+                self.expect(b']', "unterminated structure")?;
+                Ok(Value::Structure(structure))
+            }
+            Some(b'[') if is_caps_type(type_name) => {
+                self.pos += 1;
+                let caps = self.caps()?;
+                self.expect(b']', "unterminated caps")?;
+                Ok(Value::Caps(caps))
+            }
+            Some(b'[') => {
+                let mut values = self.values(type_name, b']')?.into_iter();
+                match (values.next(), values.next(), values.next(), values.next()) {
+                    (Some(min), Some(max), step, None) => Ok(Value::Range {
+                        min: Box::new(min),
+                        max: Box::new(max),
+                        step: step.map(Box::new),
+                    }),
+                    _ => self.error("invalid range"),
+                }
+            }
+            Some(b'{') => Ok(Value::List(self.values(type_name, b'}')?)),
+            Some(b'<') => Ok(Value::Array(self.values(type_name, b'>')?)),
+            Some(b'"') => {
+                let start = self.pos;
+                let text = self.quoted_string()?;
+                convert(type_name, text, true).map_err(|reason| ParseStructureError {
+                    position: start,
+                    reason,
+                })
+            }
+            _ => {
+                let start = self.pos;
+                let text = self.simple_string();
+                if text.is_empty() {
+                    return self.error("missing value");
+                }
+                convert(type_name, text.to_string(), false).map_err(|reason| ParseStructureError {
+                    position: start,
+                    reason,
+                })
+            }
+        }
+    }
+}
+
+// Write @s, quoting and escaping it if needed, as gst_string_wrap()
+fn write_string(f: &mut fmt::Formatter, s: &str, force_quotes: bool) -> fmt::Result {
+    let bytes = s.as_bytes();
+    if !force_quotes && !bytes.is_empty() && s != "NULL" && bytes.iter().all(|c| is_string_char(*c))
+    {
+        return f.write_str(s);
+    }
+
+    f.write_str("\"")?;
+    for c in bytes {
+        if is_string_char(*c) {
+            write!(f, "{}", *c as char)?;
+        } else if *c < 0x20 || *c >= 0x7f {
+            write!(f, "\\{:03o}", c)?;
+        } else {
+            write!(f, "\\{}", *c as char)?;
+        }
+    }
+    f.write_str("\"")
+}
+
+fn write_values(f: &mut fmt::Formatter, values: &[Value], start: &str, end: &str) -> fmt::Result {
+    f.write_str(start)?;
+    for (i, value) in values.iter().enumerate() {
+        if i > 0 {
+            f.write_str(",")?;
+        }
+        write!(f, " {}", value)?;
+    }
+    write!(f, " {}", end)
+}
+
+impl Value {
+    /// Name of the type of the value, as written in serialized structures.
+    /// The type of a range, list or array is the one of its first value.
+    pub fn type_name(&self) -> Option<&str> {
+        match self {
+            Value::Bool(_) => Some("boolean"),
+            Value::Int(_) => Some("int"),
+            Value::UInt(_) => Some("uint"),
+            Value::Int64(_) => Some("gint64"),
+            Value::UInt64(_) => Some("guint64"),
+            Value::Float(_) => Some("float"),
+            Value::Double(_) => Some("double"),
+            Value::String(_) => Some("string"),
+            Value::Fraction(..) => Some("fraction"),
+            Value::Range { min, .. } => min.type_name(),
+            Value::List(values) | Value::Array(values) => {
+                values.first().and_then(|value| value.type_name())
+            }
+            Value::Structure(_) => Some("structure"),
+            Value::Caps(_) => Some("GstCaps"),
+            Value::Other { type_name, .. } => Some(type_name),
+        }
+    }
+
+    // Integer values, whatever their type
+    fn integer(&self) -> Option<i128> {
+        match *self {
+            Value::Int(v) => Some(i128::from(v)),
+            Value::UInt(v) => Some(i128::from(v)),
+            Value::Int64(v) => Some(i128::from(v)),
+            Value::UInt64(v) => Some(i128::from(v)),
+            _ => None,
+        }
+    }
+}
+
+/// Display the value without its type
+impl fmt::Display for Value {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Value::Bool(v) => write!(f, "{}", v),
+            Value::Int(v) => write!(f, "{}", v),
+            Value::UInt(v) => write!(f, "{}", v),
+            Value::Int64(v) => write!(f, "{}", v),
+            Value::UInt64(v) => write!(f, "{}", v),
+            Value::Float(v) => write!(f, "{}", v),
+            Value::Double(v) => write!(f, "{}", v),
+            Value::String(s) => write_string(f, s, false),
+            Value::Fraction(num, denom) => write!(f, "{}/{}", num, denom),
+            Value::Range { min, max, step } => match step {
+                Some(step) => write!(f, "[ {}, {}, {} ]", min, max, step),
+                None => write!(f, "[ {}, {} ]", min, max),
+            },
+            Value::List(values) => write_values(f, values, "{", "}"),
+            Value::Array(values) => write_values(f, values, "<", ">"),
+            Value::Structure(s) => write_string(f, &s.to_string(), true),
+            Value::Caps(caps) => write_string(f, &caps.to_string(), true),
+            Value::Other { value, quoted, .. } => write_string(f, value, *quoted),
+        }
+    }
+}
+
+/// Conversion from a `Value` reference, used by `Structure::get()`.
+/// Integers are converted to any integer type able to represent them.
+pub trait FromValue<'a>: Sized {
+    fn from_value(value: &'a Value) -> Option<Self>;
+}
+
+macro_rules! impl_from_value_integer {
+    ($($t:ty),*) => {
+        $(
+            impl<'a> FromValue<'a> for $t {
+                fn from_value(value: &'a Value) -> Option<$t> {
+                    value.integer().and_then(|v| <$t>::try_from(v).ok())
+                }
+            }
+        )*
+    };
+}
+
+impl_from_value_integer!(i32, u32, i64, u64, usize);
+
+impl<'a> FromValue<'a> for bool {
+    fn from_value(value: &'a Value) -> Option<bool> {
+        match *value {
+            Value::Bool(v) => Some(v),
+            _ => None,
+        }
+    }
+}
+
+impl<'a> FromValue<'a> for f64 {
+    fn from_value(value: &'a Value) -> Option<f64> {
+        match *value {
+            Value::Double(v) => Some(v),
+            Value::Float(v) => Some(f64::from(v)),
+            _ => None,
+        }
+    }
+}
+
+impl<'a> FromValue<'a> for &'a str {
+    fn from_value(value: &'a Value) -> Option<&'a str> {
+        match value {
+            Value::String(s) => Some(s.as_str()),
+            _ => None,
+        }
+    }
+}
+
+impl<'a> FromValue<'a> for String {
+    fn from_value(value: &'a Value) -> Option<String> {
+        <&str>::from_value(value).map(str::to_string)
+    }
+}
+
+impl<'a> FromValue<'a> for &'a Structure {
+    fn from_value(value: &'a Value) -> Option<&'a Structure> {
+        match value {
+            Value::Structure(s) => Some(s),
+            _ => None,
+        }
+    }
+}
+
+impl<'a> FromValue<'a> for &'a Caps {
+    fn from_value(value: &'a Value) -> Option<&'a Caps> {
+        match value {
+            Value::Caps(caps) => Some(caps),
+            _ => None,
+        }
+    }
+}
+
+impl Structure {
+    pub fn new(name: &str) -> Structure {
+        Structure {
+            name: name.to_string(),
+            fields: Vec::new(),
+        }
+    }
+
+    /// Parse a serialized structure, such as `name, field=(int)1;`
+    pub fn parse(s: &str) -> ParseResult<Structure> {
+        let mut parser = Parser::new(s);
+        let (structure, _) = parser.structure(false)?;
+        if !parser.at_end() {
+            return parser.error("trailing characters");
+        }
+        Ok(structure)
+    }
+
+    /// Same as `parse()` but discarding the error, as `gst::Structure::from_string()`
+    pub fn from_string(s: &str) -> Option<Structure> {
+        Structure::parse(s).ok()
+    }
+
+    pub fn get_name(&self) -> &str {
+        &self.name
+    }
+
+    pub fn get_value(&self, field: &str) -> Option<&Value> {
+        self.fields
+            .iter()
+            .find(|(name, _)| name == field)
+            .map(|(_, value)| value)
+    }
+
+    /// Get the value of @field, if it exists and can be converted to `T`
+    pub fn get<'a, T: FromValue<'a>>(&'a self, field: &str) -> Option<T> {
+        self.get_value(field).and_then(T::from_value)
+    }
+
+    pub fn has_field(&self, field: &str) -> bool {
+        self.get_value(field).is_some()
+    }
+
+    /// Set @field to @value, replacing its existing value if any
+    pub fn set(&mut self, field: &str, value: Value) {
+        match self.fields.iter_mut().find(|(name, _)| name == field) {
+            Some(existing) => existing.1 = value,
+            None => self.fields.push((field.to_string(), value)),
+        }
+    }
+
+    pub fn fields(&self) -> impl Iterator<Item = (&str, &Value)> {
+        self.fields
+            .iter()
+            .map(|(name, value)| (name.as_str(), value))
+    }
+
+    fn write_fields(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        for (name, value) in self.fields.iter() {
+            write!(f, ", {}=", name)?;
+            if let Some(type_name) = value.type_name() {
+                write!(f, "({})", type_name)?;
+            }
+            write!(f, "{}", value)?;
+        }
+        Ok(())
+    }
+}
+
+impl fmt::Display for Structure {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.write_str(&self.name)?;
+        self.write_fields(f)?;
+        f.write_str(";")
+    }
+}
+
+impl Caps {
+    /// Parse serialized caps, such as `video/x-raw, format=(string)I420; video/x-bayer`
+    pub fn parse(s: &str) -> ParseResult<Caps> {
+        match s.trim() {
+            "ANY" => return Ok(Caps::Any),
+            "EMPTY" | "NONE" | "" => return Ok(Caps::Structures(Vec::new())),
+            _ => {}
+        }
+
+        let mut parser = Parser::new(s);
+        let caps = parser.caps()?;
+        if !parser.at_end() {
+            return parser.error("trailing characters");
+        }
+        Ok(caps)
+    }
+
+    pub fn from_string(s: &str) -> Option<Caps> {
+        Caps::parse(s).ok()
+    }
+}
+
+impl fmt::Display for Caps {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Caps::Any => f.write_str("ANY"),
+            Caps::Structures(structures) if structures.is_empty() => f.write_str("EMPTY"),
+            Caps::Structures(structures) => {
+                for (i, (structure, features)) in structures.iter().enumerate() {
+                    if i > 0 {
+                        f.write_str("; ")?;
+                    }
+                    f.write_str(structure.get_name())?;
+                    if let Some(features) = features {
+                        write!(f, "({})", features)?;
+                    }
+                    structure.write_fields(f)?;
+                }
+                Ok(())
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn round_trip(s: &str) -> Structure {
+        let structure = Structure::parse(s).unwrap();
+        assert_eq!(structure.to_string(), s);
+        structure
+    }
+
+    #[test]
+    fn tracers() {
+        let s = round_trip("latency, src=(string)videotestsrc0_src, sink=(string)fakesink0_sink, time=(guint64)4297, ts=(guint64)1004213;");
+        assert_eq!(s.get_name(), "latency");
+        assert_eq!(s.get::<&str>("src"), Some("videotestsrc0_src"));
+        assert_eq!(s.get::<u64>("time"), Some(4297));
+        assert_eq!(s.get::<u32>("time"), Some(4297));
+        assert_eq!(s.get::<bool>("time"), None);
+        assert_eq!(s.get::<u64>("nope"), None);
+
+        let s = round_trip("new-pad, ix=(uint)3, parent-ix=(uint)1, name=(string)src, template=(string)src, direction=(int)1, is-ghostpad=(boolean)false;");
+        assert_eq!(s.get::<u32>("parent-ix"), Some(1));
+        assert_eq!(s.get::<bool>("is-ghostpad"), Some(false));
+
+        let s = round_trip("buffer, thread-id=(guint64)140203452606976, ts=(guint64)1052213, pad-ix=(uint)3, element-ix=(uint)1, buffer-pts=(guint64)0, buffer-dts=(guint64)18446744073709551615, buffer-duration=(guint64)33333333, buffer-flags=(GstBufferFlags)discont, have-buffer-pts=(boolean)true, have-buffer-dts=(boolean)false;");
+        assert_eq!(s.get::<u64>("buffer-dts"), Some(u64::MAX));
+        assert_eq!(
+            s.get_value("buffer-flags"),
+            Some(&Value::Other {
+                type_name: "GstBufferFlags".to_string(),
+                value: "discont".to_string(),
+                quoted: false
+            })
+        );
+
+        round_trip("EmptyThisBuffer, TimeStamp=(guint64)33333, FilledLen=(uint)4096, name=(string)\"OMX.qcom.video\\ decoder\";");
+    }
+
+    #[test]
+    fn values() {
+        let s = round_trip("test, range=(int)[ 1, 10 ], step=(int)[ 0, 100, 4 ], list=(string){ I420, NV12 }, array=(double)< 0.5, 1.5 >, fps=(fraction)30/1, fps-range=(fraction)[ 0/1, 2147483647/1 ], empty=(string)\"\", s=(string)\"a\\\"b\\ c\\303\\251\";");
+        assert_eq!(
+            s.get_value("list"),
+            Some(&Value::List(vec![
+                Value::String("I420".to_string()),
+                Value::String("NV12".to_string())
+            ]))
+        );
+        assert_eq!(s.get_value("fps"), Some(&Value::Fraction(30, 1)));
+        assert_eq!(s.get::<&str>("s"), Some("a\"b cé"));
+        assert_eq!(s.get::<&str>("empty"), Some(""));
+
+        // Untyped values
+        let s = Structure::parse("test, a=1, b=1.5, c=30/1, d=true, e=foo, f=\"bar\"").unwrap();
+        assert_eq!(s.get_value("a"), Some(&Value::Int(1)));
+        assert_eq!(s.get_value("b"), Some(&Value::Double(1.5)));
+        assert_eq!(s.get_value("c"), Some(&Value::Fraction(30, 1)));
+        assert_eq!(s.get_value("d"), Some(&Value::Bool(true)));
+        assert_eq!(s.get::<&str>("e"), Some("foo"));
+        assert_eq!(s.get::<&str>("f"), Some("bar"));
+
+        // Hexadecimal values
+        let s = Structure::parse("test, a=(int)0x10, b=(int)-0x10, c=(uint)0xffffffff, d=(guint64)0xffffffffffffffff, e=(gint64)-0x8000000000000000").unwrap();
+        assert_eq!(s.get_value("a"), Some(&Value::Int(16)));
+        assert_eq!(s.get_value("b"), Some(&Value::Int(-16)));
+        assert_eq!(s.get_value("c"), Some(&Value::UInt(u32::MAX)));
+        assert_eq!(s.get_value("d"), Some(&Value::UInt64(u64::MAX)));
+        assert_eq!(s.get_value("e"), Some(&Value::Int64(i64::MIN)));
+        assert_eq!(
+            Structure::parse("test, a=(int)0xffffffff;")
+                .unwrap_err()
+                .reason,
+            "invalid value"
+        );
+    }
+
+    #[test]
+    fn nested() {
+        let s = round_trip("event, caps=(GstCaps)\"video/x-raw\\,\\ format\\=\\(string\\)I420\\,\\ width\\=\\(int\\)320\", info=(structure)\"info\\,\\ a\\=\\(int\\)1\\;\";");
+        let caps: &Caps = s.get("caps").unwrap();
+        match caps {
+            Caps::Structures(structures) => {
+                assert_eq!(structures.len(), 1);
+                assert_eq!(structures[0].0.get::<i32>("width"), Some(320));
+            }
+            _ => panic!("wrong caps"),
+        }
+        let info: &Structure = s.get("info").unwrap();
+        assert_eq!(info.get::<i32>("a"), Some(1));
+
+        // GStreamer >= 1.20 syntax
+        let s = Structure::parse("event, info=(structure)[info, a=(int)1];").unwrap();
+        let info: &Structure = s.get("info").unwrap();
+        assert_eq!(info.get::<i32>("a"), Some(1));
+    }
+
+    #[test]
+    fn caps() {
+        for caps in &[
+            "ANY",
+            "EMPTY",
+            "video/x-raw, format=(string){ I420, NV12 }, framerate=(fraction)[ 0/1, 2147483647/1 ]; video/x-raw(memory:GLMemory), format=(string)RGBA",
+        ] {
+            assert_eq!(Caps::parse(caps).unwrap().to_string(), *caps);
+        }
+    }
+
+    #[test]
+    fn errors() {
+        assert_eq!(
+            Structure::parse("test, a=(int)nope;"),
+            Err(ParseStructureError {
+                position: 13,
+                reason: "invalid value"
+            })
+        );
+        assert_eq!(
+            Structure::parse("test, a=(int)1 b=2").unwrap_err().reason,
+            "expected ',' or ';'"
+        );
+        assert_eq!(
+            Structure::parse("test, a=\"open").unwrap_err().reason,
+            "unterminated string"
+        );
+        assert_eq!(
+            Structure::parse("test, a=[ 1 ]").unwrap_err().reason,
+            "invalid range"
+        );
+        assert_eq!(
+            Structure::parse("1test").unwrap_err().reason,
+            "invalid structure name"
+        );
+        assert!(Structure::from_string("Initializing GStreamer Core Library").is_none());
+
+        let nested =
+            |depth: usize| format!("test, a={}1{}", "{ ".repeat(depth), " }".repeat(depth));
+        assert!(Structure::parse(&nested(MAX_DEPTH - 1)).is_ok());
+        assert_eq!(
+            Structure::parse(&nested(MAX_DEPTH)).unwrap_err().reason,
+            "too many nested values"
+        );
+        assert_eq!(
+            Structure::parse(&nested(100_000)).unwrap_err().reason,
+            "too many nested values"
+        );
+    }
+}
@@ -0,0 +1,212 @@
+use std::fmt;
+use std::io;
+use std::io::Write;
+
+use crate::datetime;
+use crate::{ClockTime, DebugLevel, Entry};
+
+const CLEAR: &str = "\x1b[00m";
+
+// Colors of the levels, indexed by their value in GStreamer's GstDebugLevel
+fn level_color(level: DebugLevel) -> &'static str {
+    match level {
+        DebugLevel::None => "\x1b[37m",
+        DebugLevel::Error => "\x1b[31;01m",
+        DebugLevel::Warning => "\x1b[33;01m",
+        DebugLevel::Fixme => "\x1b[32;01m",
+        DebugLevel::Info => "\x1b[36m",
+        DebugLevel::Debug => "\x1b[37m",
+        DebugLevel::Log => "\x1b[33;01m",
+        DebugLevel::Trace => "\x1b[37m",
+        DebugLevel::Memdump => "\x1b[37m",
+    }
+}
+
+// Colors registered by GStreamer core for its categories, other categories are assumed
+// to not have any.
+fn category_color(category: &str) -> &'static str {
+    match category {
+        "GST_INIT" | "GST_STATES" => "\x1b[00;01;31m",
+        "GST_AUTOPLUG" | "GST_CAPS" | "GST_EVENT" | "GST_MEMORY" | "GST_NEGOTIATION" => {
+            "\x1b[00;01;34m"
+        }
+        "GST_AUTOPLUG_ATTEMPT" => "\x1b[00;01;36;44m",
+        "GST_ELEMENT_PADS" | "GST_MESSAGE" | "GST_PARENTAGE" | "GST_PERFORMANCE"
+        | "GST_PIPELINE" | "GST_SIGNAL" => "\x1b[00;01;37;41m",
+        "GST_SCHEDULING" => "\x1b[00;01;35m",
+        "GST_BUFFER" | "GST_BUFFER_LIST" => "\x1b[00;01;42m",
+        "GST_BUS" => "\x1b[00;43m",
+        "GST_CLOCK" => "\x1b[00;01;33m",
+        "GST_PADS" | "GST_REFCOUNTING" => "\x1b[00;01;31;44m",
+        "GST_PLUGIN_INFO" | "GST_PLUGIN_LOADING" => "\x1b[00;01;36m",
+        "GST_PROPERTIES" => "\x1b[00;01;37;44m",
+        "GST_ERROR_SYSTEM" => "\x1b[00;01;31;47m",
+        "GST_PARAMS" => "\x1b[00;01;30;43m",
+        "GST_CALL_TRACE" => "\x1b[00;01m",
+        "GST_PROBE" => "\x1b[00;01;32m",
+        "default" => "\x1b[00;04m",
+        _ => CLEAR,
+    }
+}
+
+// Same as GST_TIME_FORMAT
+struct Time(ClockTime);
+
+impl fmt::Display for Time {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self.0.nseconds() {
+            None => f.write_str("99:99:99.999999999"),
+            Some(t) => write!(
+                f,
+                "{}:{:02}:{:02}.{:09}",
+                t / 3_600_000_000_000,
+                (t / 60_000_000_000) % 60,
+                (t / 1_000_000_000) % 60,
+                t % 1_000_000_000
+            ),
+        }
+    }
+}
+
+/// Display an entry using the same layout as GStreamer's default log function
+pub(crate) struct Canonical<'a> {
+    pub(crate) entry: &'a Entry,
+    pub(crate) colors: bool,
+}
+
+impl<'a> fmt::Display for Canonical<'a> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let entry = self.entry;
+        let (pid_color, level_color, category_color, clear) = if self.colors {
+            (
+                // GStreamer uses "\033[3%1dm" for the pid color
+                format!("\x1b[3{}m", entry.pid % 6 + 31),
+                level_color(entry.level),
+                category_color(&entry.category),
+                CLEAR,
+            )
+        } else {
+            (String::new(), "", "", "")
+        };
+
+        if let Some(wallclock) = entry.wallclock {
+            write!(f, "{} ", datetime::format_datetime(wallclock))?;
+        }
+
+        write!(
+            f,
+            "{} {}{:>5}{} {:>14} {}{:<7}{} {}{:>20} {}:{}:{}:",
+            Time(entry.ts),
+            pid_color,
+            entry.pid,
+            clear,
+            entry.thread,
+            level_color,
+            entry.level.as_str(),
+            clear,
+            category_color,
+            entry.category,
+            entry.file,
+            entry.line,
+            entry.function,
+        )?;
+        if let Some(ref object) = entry.object {
+            write!(f, "<{}>", object)?;
+        }
+        write!(f, "{} {}", clear, entry.message)?;
+
+        for line in entry.continuation.iter() {
+            write!(f, "\n{}", line)?;
+        }
+        Ok(())
+    }
+}
+
+/// Write entries using the same layout as GStreamer's default log function, so the output
+/// can be parsed again. Metadata from logcat or the journal is not written.
+pub struct Writer<W: Write> {
+    writer: W,
+    colors: bool,
+}
+
+impl<W: Write> Writer<W> {
+    pub fn new(writer: W) -> Self {
+        Self {
+            writer,
+            colors: false,
+        }
+    }
+
+    /// Color entries as GStreamer does when writing to a terminal, disabled by default
+    pub fn colors(mut self, colors: bool) -> Self {
+        self.colors = colors;
+        self
+    }
+
+    pub fn write(&mut self, entry: &Entry) -> io::Result<()> {
+        let colors = self.colors;
+        writeln!(self.writer, "{}", Canonical { entry, colors })
+    }
+
+    pub fn flush(&mut self) -> io::Result<()> {
+        self.writer.flush()
+    }
+
+    pub fn into_inner(self) -> W {
+        self.writer
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{parse, Multiline};
+    use std::fs;
+    use std::time::{Duration, UNIX_EPOCH};
+
+    fn round_trip(path: &str, colors: bool) {
+        let original = fs::read(path).expect("Failed to read log file");
+        let entries: Vec<Entry> = parse(&original[..]).collect();
+
+        let mut writer = Writer::new(Vec::new()).colors(colors);
+        for entry in entries.iter() {
+            writer.write(entry).unwrap();
+        }
+        let written = writer.into_inner();
+
+        assert_eq!(
+            String::from_utf8_lossy(&written),
+            String::from_utf8_lossy(&original)
+        );
+        assert_eq!(parse(&written[..]).collect::<Vec<Entry>>(), entries);
+    }
+
+    #[test]
+    fn nocolor() {
+        round_trip("test-logs/nocolor.log", false);
+    }
+
+    #[test]
+    fn color() {
+        round_trip("test-logs/color.log", true);
+    }
+
+    #[test]
+    fn extra() {
+        let log = "2019-06-04T10:11:12.000001000Z 0:00:00.007773544  8874 0x558951015c00 MEMDUMP           GST_BUFFER gstbuffer.c:1:dump:<GstBuffer@0x1234> dumping\n\
+                   00000000: 00 01 02 03  ....\n";
+
+        let entries: Vec<Entry> = crate::try_parse(log.as_bytes())
+            .multiline(Multiline::Split)
+            .filter_map(Result::ok)
+            .collect();
+        assert_eq!(
+            entries[0].wallclock,
+            Some(UNIX_EPOCH + Duration::new(1_559_643_072, 1000))
+        );
+
+        let mut writer = Writer::new(Vec::new());
+        writer.write(&entries[0]).unwrap();
+        assert_eq!(String::from_utf8(writer.into_inner()).unwrap(), log);
+    }
+}